@@ -1,46 +1,87 @@
 #![no_std]
 use atsamd_hal::target_device::SDHC0;
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
 use embedded_error::mci::CommandOrDataError;
 use embedded_error::mci::MciError;
 use embedded_error::ImplError;
+#[cfg(feature = "async")]
+use mci::async_mci::{yield_now, AsyncMci};
 use mci::command_arguments::mci_command::MciCommand;
 use mci::command_arguments::mmc::BusWidth;
 use mci::mci::Mci;
+use mci::timeout::{MonotonicClock, NoClock, Timeout};
 
-pub struct AtsamdMci {
+/// Time the card may hold the data line busy after a command, by default
+pub const BUSY_TIMEOUT_US: u32 = 1_000_000;
+
+pub struct AtsamdMci<CLOCK = NoClock>
+where
+    CLOCK: MonotonicClock,
+{
     sdhc: SDHC0,
     trans_pos: u64,
     block_size: u16,
     block_amount: u16,
+    clock: Option<CLOCK>,
+    busy_timeout_us: u32,
 }
 
 impl AtsamdMci {
     pub fn new(sdhc: SDHC0) -> AtsamdMci {
+        AtsamdMci::create(sdhc, None)
+    }
+}
+
+impl<CLOCK> AtsamdMci<CLOCK>
+where
+    CLOCK: MonotonicClock,
+{
+    /// Host whose busy waits are timed on a monotonic clock
+    pub fn new_with_monotonic_clock(sdhc: SDHC0, clock: CLOCK) -> Self {
+        AtsamdMci::create(sdhc, Some(clock))
+    }
+
+    fn create(sdhc: SDHC0, clock: Option<CLOCK>) -> Self {
         AtsamdMci {
             sdhc,
             trans_pos: 0,
             block_size: 0,
             block_amount: 0,
+            clock,
+            busy_timeout_us: BUSY_TIMEOUT_US,
         }
     }
 
-    pub fn reset(&mut self) {
+    /// Time the card may hold the data line busy after a command, in us. Defaults to
+    /// BUSY_TIMEOUT_US, an erase of many blocks needs more
+    pub fn set_busy_timeout_us(&mut self, busy_timeout_us: u32) {
+        self.busy_timeout_us = busy_timeout_us;
+    }
+
+    pub fn reset(&self) {
         self.sdhc.srr.modify(|_, w| w.swrstcmd().set_bit());
     }
 
+    /// Deadline of a busy wait
+    /// Without a monotonic clock the polls are counted: each one reads a register of the host,
+    /// which takes at least a cycle of its base clock
+    fn busy_timeout(&self) -> Timeout {
+        let base_clock = self.sdhc.ca0r.read().baseclkf().bits() as u32 * 1_000_000;
+        Timeout::new(self.clock.as_ref(), self.busy_timeout_us, base_clock, 1)
+    }
+
+    /// Wait for the card to release the data line, the host is reset on a timeout
     pub fn wait_busy(&mut self) -> Result<(), MciError> {
-        for n in (0u32..=0xFFFF_FFFFu32).rev() {
-            if n == 0 {
-                self.reset();
-                return Err(MciError::Impl(ImplError::TimedOut));
-            }
+        let mut timeout = self.busy_timeout();
+        loop {
             if self.sdhc.psr.read().datll().bits() == 0x1 {
                 return Ok(());
             }
+            if timeout.expired(self.clock.as_ref()) {
+                self.reset();
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
         }
-        unsafe { unreachable_unchecked() }
     }
 
     pub fn set_speed(&mut self, speed: u32, prog_clock_mode: u8) {
@@ -99,12 +140,17 @@ impl AtsamdMci {
     }
 
     /// Send a command
-    pub fn send_command_execute(
-        &mut self,
-        mut cmdr: u16,
-        cmd: u32,
-        arg: u32,
-    ) -> Result<(), MciError> {
+    pub fn send_command_execute(&mut self, cmdr: u16, cmd: u32, arg: u32) -> Result<(), MciError> {
+        let (cmdr, cmd) = self.send_command_start(cmdr, cmd, arg);
+        while !self.send_command_poll(&cmd)? {}
+        if self.send_command_end(cmdr, &cmd) {
+            return self.wait_busy();
+        }
+        Ok(())
+    }
+
+    /// Write the command and its argument, which starts it
+    fn send_command_start(&mut self, mut cmdr: u16, cmd: u32, arg: u32) -> (u16, MciCommand) {
         cmdr.set_bits(8..16, cmd as u16);
         let cmd: MciCommand = cmd.into();
 
@@ -131,56 +177,55 @@ impl AtsamdMci {
 
         self.sdhc.arg1r.write(|w| unsafe { w.bits(arg) });
         self.sdhc.cr.write(|w| unsafe { w.bits(cmdr) });
+        (cmdr, cmd)
+    }
 
-        loop {
-            let sr = self.sdhc.eister().read();
-            let error = error_from_eistr(
-                sr.cmdteo().bit_is_set(),
-                sr.cmdcrc().bit_is_set(),
-                sr.cmdend().bit_is_set(),
-                sr.cmdidx().bit_is_set(),
-                sr.datteo().bit_is_set(),
-                sr.datcrc().bit_is_set(),
-                sr.datend().bit_is_set(),
-                sr.adma().bit_is_set(),
-                cmd.expect_valid_crc(),
-            );
-            if error.is_some() {
-                self.reset();
-                self.sdhc.eister().write(|w| unsafe { w.bits(0x03FF) });
-                return Err(error.unwrap());
-            }
-            if self.sdhc.nistr().read().cmdc().bit_is_clear() {
-                break;
-            }
+    /// Check the command once for errors and for its end
+    fn send_command_poll(&mut self, cmd: &MciCommand) -> Result<bool, MciError> {
+        let sr = self.sdhc.eister().read();
+        let error = error_from_eistr(
+            sr.cmdteo().bit_is_set(),
+            sr.cmdcrc().bit_is_set(),
+            sr.cmdend().bit_is_set(),
+            sr.cmdidx().bit_is_set(),
+            sr.datteo().bit_is_set(),
+            sr.datcrc().bit_is_set(),
+            sr.datend().bit_is_set(),
+            sr.adma().bit_is_set(),
+            cmd.expect_valid_crc(),
+        );
+        if let Some(error) = error {
+            self.reset();
+            self.sdhc.eister().write(|w| unsafe { w.bits(0x03FF) });
+            return Err(error);
         }
+        Ok(self.sdhc.nistr().read().cmdc().bit_is_clear())
+    }
 
+    /// Acknowledge the end of the command
+    /// Returns whether the card may still signal busy
+    fn send_command_end(&mut self, cmdr: u16, cmd: &MciCommand) -> bool {
         if !cmdr.get_bit(5) {
             self.sdhc.nistr().write(|w| w.cmdc().set_bit());
         }
-
-        if cmd.card_may_send_busy() {
-            return self.wait_busy();
-        }
-
-        Ok(())
+        cmd.card_may_send_busy()
     }
 
-    pub fn eistr_err(&mut self) -> Result<(), MciError> {
+    pub fn eistr_err(&self) -> Result<(), MciError> {
         let sr = self.sdhc.eistr().read();
         let error = command_error_from_eistr(
             sr.datteo().bit_is_set(),
             sr.datcrc().bit_is_set(),
             sr.datend().bit_is_set(),
         );
-        if error.is_some() {
+        if let Some(error) = error {
             self.reset();
-            return Err(MciError::CommandError(error.unwrap()));
+            return Err(MciError::CommandError(error));
         }
         Ok(())
     }
 
-    pub fn loop_or_on_eistr_err<F: FnMut(&mut AtsamdMci) -> bool>(
+    pub fn loop_or_on_eistr_err<F: FnMut(&mut Self) -> bool>(
         &mut self,
         mut f: F,
     ) -> Result<(), MciError> {
@@ -192,6 +237,145 @@ impl AtsamdMci {
         }
         Ok(())
     }
+
+    /// Whether the next word starts a block, and how many bytes of the block it holds
+    /// The last word of a block is partial when the block size is not a multiple of 4
+    fn word_position(&self) -> (bool, u8) {
+        let block_position = self.trans_pos % (self.block_size as u64);
+        let nbytes = ((self.block_size as u64) - block_position).min(4) as u8;
+        (block_position == 0, nbytes)
+    }
+
+    /// Read a word of the data port, keeping the bytes that belong to the block
+    fn read_data_port(&mut self, nbytes: u8) -> u32 {
+        let val = self.sdhc.bdpr.read().bits()
+            & match nbytes {
+                3 => 0xFF_FFFF,
+                2 => 0xFFFF,
+                1 => 0xFF,
+                _ => 0xFFFF_FFFF,
+            };
+        self.trans_pos += nbytes as u64;
+        val
+    }
+
+    /// Write a word to the data port
+    fn write_data_port(&mut self, val: u32, nbytes: u8) {
+        self.sdhc.bdpr.write(|w| unsafe { w.bits(val) });
+        self.trans_pos += nbytes as u64;
+    }
+
+    /// Whether all the blocks of the transfer went through the data port
+    /// A block amount of 0 is an infinite transfer, which ends with an abort
+    fn transfer_ended(&self) -> bool {
+        self.block_amount != 0
+            && (self.block_size as u64) * (self.block_amount as u64) <= self.trans_pos
+    }
+
+    /// Whether the data transfer is complete, which for a write includes the card busy
+    /// Command inhibit (DAT) clears with the transfer complete flag, and stays clear once the flag
+    /// is acknowledged
+    fn transfer_complete(&self) -> bool {
+        if self.sdhc.psr.read().cmdinhd().bit_is_set() {
+            return false;
+        }
+        self.sdhc.nistr().modify(|_, w| w.trfc().yes());
+        true
+    }
+
+    /// Wait end of transfer, once all the data of a transfer with a block count is moved
+    fn wait_transfer_complete(&self) -> Result<(), MciError> {
+        if !self.transfer_ended() {
+            return Ok(());
+        }
+        loop {
+            self.eistr_err()?;
+            if self.transfer_complete() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Start an ADTC command, with an optional auto CMD23 argument
+    fn adtc_start_execute(
+        &mut self,
+        command: u32,
+        argument: u32,
+        block_size: u16,
+        block_amount: u16,
+        cmd23_argument: Option<u32>,
+    ) -> Result<(), MciError> {
+        let command = self.adtc_setup(command, block_size, block_amount, cmd23_argument)?;
+        self.send_command_execute(1 << 5, command, argument)
+    }
+
+    /// Set up the transfer of an ADTC command
+    /// Returns the command to send
+    fn adtc_setup(
+        &mut self,
+        command: u32,
+        block_size: u16,
+        block_amount: u16,
+        cmd23_argument: Option<u32>,
+    ) -> Result<u32, MciError> {
+        let psr = self.sdhc.psr.read();
+        // Check Command Inhibit (CMD/DAT) in the Present State register
+        if psr.cmdinhc().bit_is_set() || psr.cmdinhd().bit_is_set() {
+            return Err(MciError::CommandInhibited);
+        }
+
+        let command: MciCommand = command.into();
+        if !command.sdio_multi_byte_transfer()
+            && !command.sdio_block_mode_transfer()
+            && !command.single_block_data_transfer()
+            && !command.multi_block_data_transfer()
+        {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+
+        self.sdhc.tmr.write(|w| {
+            if command.data_write_command() {
+                w.dtdsel().write();
+            } else {
+                w.dtdsel().read();
+            }
+            if command.sdio_multi_byte_transfer() {
+                w.msbsel().single();
+            } else if command.sdio_block_mode_transfer() {
+                // No block count for an infinite SDIO transfer
+                if block_amount != 0 {
+                    w.bcen().enable();
+                }
+                w.msbsel().multiple();
+            } else if command.single_block_data_transfer() {
+                w.msbsel().single();
+            } else if command.multi_block_data_transfer() {
+                w.bcen().enable().msbsel().multiple();
+            }
+            if cmd23_argument.is_some() {
+                w.acmden().cmd23();
+            }
+            w
+        });
+
+        if let Some(cmd23_argument) = cmd23_argument {
+            self.sdhc
+                .ssar_cmd23()
+                .write(|w| unsafe { w.arg2().bits(cmd23_argument) });
+        }
+
+        self.sdhc
+            .bsr
+            .write(|w| unsafe { w.blocksize().bits(block_size) }.boundary()._4k());
+        self.sdhc
+            .bcr
+            .write(|w| unsafe { w.bcnt().bits(block_amount) });
+
+        self.block_amount = block_amount;
+        self.block_size = block_size;
+        self.trans_pos = 0;
+        Ok(command.val)
+    }
 }
 
 fn command_error_from_eistr(timeout: bool, crc: bool, end: bool) -> Option<CommandOrDataError> {
@@ -206,6 +390,7 @@ fn command_error_from_eistr(timeout: bool, crc: bool, end: bool) -> Option<Comma
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn error_from_eistr(
     cmd_timeout: bool,
     cmd_crc: bool,
@@ -240,7 +425,10 @@ fn error_from_eistr(
     }
 }
 
-impl Mci for AtsamdMci {
+impl<CLOCK> Mci for AtsamdMci<CLOCK>
+where
+    CLOCK: MonotonicClock,
+{
     fn init(&mut self) -> Result<(), MciError> {
         self.sdhc.srr.modify(|_, w| w.swrstall().set_bit());
         loop {
@@ -319,6 +507,70 @@ impl Mci for AtsamdMci {
         Ok(self.sdhc.ca0r.read().hssup().bit_is_set())
     }
 
+    fn set_sdio_interrupt_enabled(&mut self, enabled: bool) -> Result<(), MciError> {
+        self.sdhc.nister().modify(|_, w| w.cint().bit(enabled));
+        self.sdhc.nisier().modify(|_, w| w.cint().bit(enabled));
+        Ok(())
+    }
+
+    fn is_sdio_interrupt_pending(&mut self) -> Result<bool, MciError> {
+        Ok(self.sdhc.nistr().read().cint().bit_is_set())
+    }
+
+    fn is_sdio_interrupt_at_block_gap_capable(&mut self) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
+    fn set_sdio_interrupt_at_block_gap(&mut self, enabled: bool) -> Result<(), MciError> {
+        self.sdhc.bgcr().modify(|_, w| w.intbg().bit(enabled));
+        Ok(())
+    }
+
+    fn stop_at_block_gap(&mut self) -> Result<(), MciError> {
+        if self.sdhc.psr.read().dlact().bit_is_clear() {
+            return Ok(());
+        }
+        self.sdhc.bgcr().modify(|_, w| w.stpbgr().set_bit());
+        self.loop_or_on_eistr_err(|f| f.sdhc.nistr().read().blkge().bit_is_set())?;
+        self.sdhc.nistr().write(|w| w.blkge().set_bit());
+        Ok(())
+    }
+
+    fn continue_at_block_gap(&mut self) -> Result<(), MciError> {
+        if self.sdhc.bgcr().read().stpbgr().bit_is_clear() {
+            return Ok(());
+        }
+        self.sdhc
+            .bgcr()
+            .modify(|_, w| w.stpbgr().clear_bit().contr().set_bit());
+        Ok(())
+    }
+
+    fn reset_data_line(&mut self) -> Result<(), MciError> {
+        self.sdhc.srr.modify(|_, w| w.swrstdat().set_bit());
+        loop {
+            if self.sdhc.srr.read().swrstdat().bit_is_clear() {
+                break;
+            }
+        }
+        self.trans_pos = 0;
+        Ok(())
+    }
+
+    fn reset_host(&mut self) -> Result<(), MciError> {
+        self.sdhc
+            .srr
+            .modify(|_, w| w.swrstcmd().set_bit().swrstdat().set_bit());
+        loop {
+            let srr = self.sdhc.srr.read();
+            if srr.swrstcmd().bit_is_clear() && srr.swrstdat().bit_is_clear() {
+                break;
+            }
+        }
+        self.trans_pos = 0;
+        Ok(())
+    }
+
     /// Send 74 clock cycles on the line.
     /// Note: It is required after card plug and before card install.
     fn send_clock(&mut self) -> Result<(), MciError> {
@@ -349,51 +601,29 @@ impl Mci for AtsamdMci {
         block_amount: u16,
         _access_in_blocks: bool,
     ) -> Result<(), MciError> {
-        let psr = self.sdhc.psr.read();
-        // Check Command Inhibit (CMD/DAT) in the Present State register
-        if psr.cmdinhc().bit_is_set() || psr.cmdinhd().bit_is_set() {
-            return Err(MciError::CommandInhibited);
-        }
-
-        let command: MciCommand = command.into();
-        if !command.sdio_multi_byte_transfer()
-            && !command.sdio_block_mode_transfer()
-            && !command.single_block_data_transfer()
-            && !command.multi_block_data_transfer()
-        {
-            return Err(MciError::Impl(ImplError::InvalidConfiguration));
-        }
-
-        self.sdhc.tmr.write(|w| {
-            if command.data_write_command() {
-                w.dtdsel().write();
-            } else {
-                w.dtdsel().read();
-            }
-            if command.sdio_multi_byte_transfer() {
-                w.msbsel().single();
-            } else if command.sdio_block_mode_transfer() {
-                w.bcen().enable().msbsel().multiple();
-            } else if command.single_block_data_transfer() {
-                w.msbsel().single();
-            } else if command.multi_block_data_transfer() {
-                w.bcen().enable().msbsel().multiple();
-            }
-            w
-        });
-
-        self.sdhc
-            .bsr
-            .write(|w| unsafe { w.blocksize().bits(block_size) }.boundary()._4k());
-        self.sdhc
-            .bcr
-            .write(|w| unsafe { w.bcnt().bits(block_amount) });
+        self.adtc_start_execute(command, argument, block_size, block_amount, None)
+    }
 
-        self.block_amount = block_amount;
-        self.block_size = block_size;
-        self.trans_pos = 0;
+    fn is_auto_cmd23_capable(&mut self) -> Result<bool, MciError> {
+        Ok(true)
+    }
 
-        self.send_command_execute(1 << 5, command.val, argument)
+    fn adtc_start_with_auto_cmd23(
+        &mut self,
+        command: u32,
+        argument: u32,
+        block_size: u16,
+        block_amount: u16,
+        _access_in_blocks: bool,
+        cmd23_argument: u32,
+    ) -> Result<(), MciError> {
+        self.adtc_start_execute(
+            command,
+            argument,
+            block_size,
+            block_amount,
+            Some(cmd23_argument),
+        )
     }
 
     fn adtc_stop(&self, _command: u32, _argument: u32) -> Result<(), MciError> {
@@ -402,55 +632,19 @@ impl Mci for AtsamdMci {
     }
 
     fn read_word(&mut self) -> Result<(u32, u8), MciError> {
-        let nbytes: u8 =
-            if ((self.block_size as u64) * (self.block_amount as u64)) - self.trans_pos > 4 {
-                (self.block_size % 4) as u8
-            } else {
-                4
-            };
-
-        if self.trans_pos % (self.block_size as u64) == 0 {
+        let (block_start, nbytes) = self.word_position();
+        if block_start {
             self.loop_or_on_eistr_err(|f| f.sdhc.nistr().read().brdrdy().bit_is_set())?;
         }
-
-        // Read data
-        let val = self.sdhc.bdpr.read().bits()
-            & match nbytes {
-                3 => 0xFF_FFFF,
-                2 => 0xFFFF,
-                1 => 0xFF,
-                _ => 0xFFFF_FFFF,
-            };
-
-        self.trans_pos += nbytes as u64;
-
-        if (self.block_size as u64) * (self.block_amount as u64) > self.trans_pos {
-            return Ok((val, nbytes));
-        }
-
-        // Wait end of transfer
-        self.loop_or_on_eistr_err(|f| f.sdhc.nistr().read().trfc().bit_is_set())?;
-        self.sdhc.nistr().modify(|_, w| w.trfc().yes());
-        Ok((val, nbytes))
+        Ok((self.read_data_port(nbytes), nbytes))
     }
 
     fn write_word(&mut self, val: u32) -> Result<bool, MciError> {
-        let nbytes = 4u64; // self.block_size & 0x3 ? 1 : 4
-        if self.trans_pos % (self.block_size as u64) == 0 {
+        let (block_start, nbytes) = self.word_position();
+        if block_start {
             self.loop_or_on_eistr_err(|f| f.sdhc.nistr().read().bwrrdy().bit_is_set())?;
         }
-
-        // Write data
-        self.sdhc.bdpr.write(|w| unsafe { w.bits(val) });
-        self.trans_pos += nbytes;
-
-        if (self.block_size as u64) * (self.block_amount as u64) > self.trans_pos {
-            return Ok(true);
-        }
-
-        // Wait end of transfer
-        self.loop_or_on_eistr_err(|f| f.sdhc.nistr().read().trfc().bit_is_set())?;
-        self.sdhc.nistr().modify(|_, w| w.trfc().yes());
+        self.write_data_port(val, nbytes);
         Ok(true)
     }
 
@@ -459,61 +653,235 @@ impl Mci for AtsamdMci {
         destination: &mut [u8],
         number_of_blocks: u16,
     ) -> Result<bool, MciError> {
-        let mut data = (number_of_blocks as u64) * (self.block_size as u64);
-        let len = data as usize;
+        let len = (number_of_blocks as usize) * (self.block_size as usize);
         let mut index = 0usize;
 
-        while data > 0 {
+        while index < len {
             let (val, nbytes) = self.read_word()?;
-            for m in 0..nbytes {
-                let mm = m as usize;
-                if mm + index >= len {
-                    break;
-                }
-                destination[index + mm] = val.get_bits((mm * 8)..((mm + 1) * 8)) as u8;
+            // The data port is little endian
+            for m in 0..(nbytes as usize).min(len - index) {
+                destination[index + m] = val.get_bits((m * 8)..((m + 1) * 8)) as u8;
             }
-            let nbytes = if (nbytes as u64) > data {
-                (self.block_size % (nbytes as u16)) as u8
-            } else {
-                nbytes
-            };
             index += nbytes as usize;
-            data -= nbytes as u64;
         }
         Ok(true)
     }
 
+    fn is_read_buffer_ready(&mut self) -> Result<bool, MciError> {
+        self.eistr_err()?;
+        Ok(self.sdhc.psr.read().bufrden().bit_is_set())
+    }
+
+    fn is_write_buffer_ready(&mut self) -> Result<bool, MciError> {
+        self.eistr_err()?;
+        Ok(self.sdhc.psr.read().bufwren().bit_is_set())
+    }
+
     fn write_blocks(&mut self, write_data: &[u8], number_of_blocks: u16) -> Result<bool, MciError> {
-        let mut data = (number_of_blocks as u64) * (self.block_size as u64);
-        let len = data as usize;
+        let len = (number_of_blocks as usize) * (self.block_size as usize);
         let mut index = 0usize;
 
-        while data > 0 {
-            let mut nbytes = 0u8;
+        while index < len {
+            let block_position = (self.trans_pos % (self.block_size as u64)) as usize;
+            let nbytes = (self.block_size as usize - block_position).min(4);
+            // The data port is little endian
             let mut val = 0u32;
-            for m in 0..4 {
-                let mm = m as usize;
-                if mm + index >= len {
-                    break;
-                }
-                val <<= 8;
-                nbytes += 1;
-                val |= write_data[index + mm] as u32;
+            for m in 0..nbytes.min(len - index) {
+                val.set_bits((m * 8)..((m + 1) * 8), write_data[index + m] as u32);
             }
             self.write_word(val)?;
-            data -= nbytes as u64;
-            index += nbytes as usize;
+            index += nbytes;
         }
         Ok(true)
     }
 
+    fn is_transfer_complete(&mut self) -> Result<bool, MciError> {
+        self.eistr_err()?;
+        Ok(self.transfer_complete())
+    }
+
     fn wait_until_read_finished(&self) -> Result<(), MciError> {
-        // Nop
-        Ok(())
+        self.wait_transfer_complete()
     }
 
     fn wait_until_write_finished(&self) -> Result<(), MciError> {
+        self.wait_transfer_complete()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<CLOCK> AtsamdMci<CLOCK>
+where
+    CLOCK: MonotonicClock,
+{
+    /// Wait for the data line to be released, yielding between polls
+    pub async fn wait_busy_async(&mut self) -> Result<(), MciError> {
+        let mut timeout = self.busy_timeout();
+        loop {
+            if self.sdhc.psr.read().datll().bits() == 0x1 {
+                return Ok(());
+            }
+            if timeout.expired(self.clock.as_ref()) {
+                self.reset();
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
+            yield_now().await;
+        }
+    }
+
+    /// Send a command, yielding until its end
+    pub async fn send_command_execute_async(
+        &mut self,
+        cmdr: u16,
+        cmd: u32,
+        arg: u32,
+    ) -> Result<(), MciError> {
+        let (cmdr, cmd) = self.send_command_start(cmdr, cmd, arg);
+        while !self.send_command_poll(&cmd)? {
+            yield_now().await;
+        }
+        if self.send_command_end(cmdr, &cmd) {
+            return self.wait_busy_async().await;
+        }
+        Ok(())
+    }
+
+    /// Yield until f is true, or until a data error
+    async fn loop_or_on_eistr_err_async<F: FnMut(&mut Self) -> bool>(
+        &mut self,
+        mut f: F,
+    ) -> Result<(), MciError> {
+        loop {
+            self.eistr_err()?;
+            if f(self) {
+                return Ok(());
+            }
+            yield_now().await;
+        }
+    }
+
+    /// Wait end of transfer, yielding between polls
+    async fn wait_transfer_complete_async(&mut self) -> Result<(), MciError> {
+        if self.transfer_ended() {
+            self.loop_or_on_eistr_err_async(|f| f.transfer_complete())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn read_word_async(&mut self) -> Result<(u32, u8), MciError> {
+        let (block_start, nbytes) = self.word_position();
+        if block_start {
+            self.loop_or_on_eistr_err_async(|f| f.sdhc.nistr().read().brdrdy().bit_is_set())
+                .await?;
+        }
+        Ok((self.read_data_port(nbytes), nbytes))
+    }
+
+    async fn write_word_async(&mut self, val: u32) -> Result<(), MciError> {
+        let (block_start, nbytes) = self.word_position();
+        if block_start {
+            self.loop_or_on_eistr_err_async(|f| f.sdhc.nistr().read().bwrrdy().bit_is_set())
+                .await?;
+        }
+        self.write_data_port(val, nbytes);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<CLOCK> AsyncMci for AtsamdMci<CLOCK>
+where
+    CLOCK: MonotonicClock,
+{
+    async fn send_command_async(&mut self, cmd: u32, arg: u32) -> Result<(), MciError> {
+        if self.sdhc.psr.read().cmdinhc().bit_is_set() {
+            return Err(MciError::CommandInhibited);
+        }
+
+        self.sdhc.tmr.modify(|_, w| w.dmaen().clear_bit());
+        self.sdhc.bcr.modify(|_, w| unsafe { w.bits(0) });
+        self.send_command_execute_async(0, cmd, arg).await
+    }
+
+    async fn adtc_start_async(
+        &mut self,
+        command: u32,
+        argument: u32,
+        block_size: u16,
+        block_amount: u16,
+        _access_in_blocks: bool,
+    ) -> Result<(), MciError> {
+        let command = self.adtc_setup(command, block_size, block_amount, None)?;
+        self.send_command_execute_async(1 << 5, command, argument)
+            .await
+    }
+
+    async fn adtc_start_with_auto_cmd23_async(
+        &mut self,
+        command: u32,
+        argument: u32,
+        block_size: u16,
+        block_amount: u16,
+        _access_in_blocks: bool,
+        cmd23_argument: u32,
+    ) -> Result<(), MciError> {
+        let command = self.adtc_setup(command, block_size, block_amount, Some(cmd23_argument))?;
+        self.send_command_execute_async(1 << 5, command, argument)
+            .await
+    }
+
+    async fn adtc_stop_async(&mut self, _command: u32, _argument: u32) -> Result<(), MciError> {
         // Nop
         Ok(())
     }
+
+    async fn read_blocks_async(
+        &mut self,
+        destination: &mut [u8],
+        number_of_blocks: u16,
+    ) -> Result<bool, MciError> {
+        let len = (number_of_blocks as usize) * (self.block_size as usize);
+        let mut index = 0usize;
+
+        while index < len {
+            let (val, nbytes) = self.read_word_async().await?;
+            // The data port is little endian
+            for m in 0..(nbytes as usize).min(len - index) {
+                destination[index + m] = val.get_bits((m * 8)..((m + 1) * 8)) as u8;
+            }
+            index += nbytes as usize;
+        }
+        Ok(true)
+    }
+
+    async fn write_blocks_async(
+        &mut self,
+        write_data: &[u8],
+        number_of_blocks: u16,
+    ) -> Result<bool, MciError> {
+        let len = (number_of_blocks as usize) * (self.block_size as usize);
+        let mut index = 0usize;
+
+        while index < len {
+            let (_, nbytes) = self.word_position();
+            let nbytes = nbytes as usize;
+            // The data port is little endian
+            let mut val = 0u32;
+            for m in 0..nbytes.min(len - index) {
+                val.set_bits((m * 8)..((m + 1) * 8), write_data[index + m] as u32);
+            }
+            self.write_word_async(val).await?;
+            index += nbytes;
+        }
+        Ok(true)
+    }
+
+    async fn wait_until_read_finished_async(&mut self) -> Result<(), MciError> {
+        self.wait_transfer_complete_async().await
+    }
+
+    async fn wait_until_write_finished_async(&mut self) -> Result<(), MciError> {
+        self.wait_transfer_complete_async().await
+    }
 }
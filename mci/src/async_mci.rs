@@ -0,0 +1,90 @@
+use crate::mci::Mci;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+
+/// MCI driver whose commands and transfers are futures
+/// Only what waits on the card is async, the configuration stays the one of Mci.
+/// A driver without interrupts can poll its status and yield_now between polls
+#[allow(async_fn_in_trait)]
+pub trait AsyncMci: Mci {
+    /// Send a command and wait for its end, busy signalling included
+    async fn send_command_async(&mut self, cmd: u32, arg: u32) -> Result<(), MciError>;
+
+    /// ADTC command start, see Mci::adtc_start
+    async fn adtc_start_async(
+        &mut self,
+        command: u32,
+        argument: u32,
+        block_size: u16,
+        block_amount: u16,
+        access_in_blocks: bool,
+    ) -> Result<(), MciError>;
+
+    /// ADTC command start preceded by an auto CMD23, see Mci::adtc_start_with_auto_cmd23
+    async fn adtc_start_with_auto_cmd23_async(
+        &mut self,
+        _command: u32,
+        _argument: u32,
+        _block_size: u16,
+        _block_amount: u16,
+        _access_in_blocks: bool,
+        _cmd23_argument: u32,
+    ) -> Result<(), MciError> {
+        Err(MciError::Impl(ImplError::InvalidConfiguration))
+    }
+
+    /// ADTC command stop, see Mci::adtc_stop
+    async fn adtc_stop_async(&mut self, command: u32, argument: u32) -> Result<(), MciError>;
+
+    /// Read blocks, waiting for each block without blocking
+    /// # Arguments
+    ///  * `destination` Buffer to write to
+    ///  * `number_of_blocks` Number of blocks to read
+    async fn read_blocks_async(
+        &mut self,
+        destination: &mut [u8],
+        number_of_blocks: u16,
+    ) -> Result<bool, MciError>;
+
+    /// Write blocks, waiting for each block without blocking
+    /// # Arguments
+    ///  * `data` - Data to write on the line
+    ///  * `number_of_blocks` - Number of blocks to write
+    async fn write_blocks_async(
+        &mut self,
+        data: &[u8],
+        number_of_blocks: u16,
+    ) -> Result<bool, MciError>;
+
+    /// Wait until the end of reading the blocks
+    async fn wait_until_read_finished_async(&mut self) -> Result<(), MciError>;
+
+    /// Wait until the end of writing blocks
+    async fn wait_until_write_finished_async(&mut self) -> Result<(), MciError>;
+}
+
+/// Future that is pending once, to let the executor run other tasks
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Give the executor a chance to run other tasks between two polls of the card
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
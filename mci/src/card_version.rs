@@ -26,9 +26,9 @@ pub enum MmcVersion {
     Mmc4d0 = 0x40,
 }
 
-impl Into<Option<MmcVersion>> for SdCardVersion {
-    fn into(self) -> Option<MmcVersion> {
-        if self == SdCardVersion::SdMmc3d0 {
+impl From<SdCardVersion> for Option<MmcVersion> {
+    fn from(val: SdCardVersion) -> Self {
+        if val == SdCardVersion::SdMmc3d0 {
             Some(MmcVersion::SdMmc3d0)
         } else {
             None
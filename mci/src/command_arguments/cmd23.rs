@@ -0,0 +1,35 @@
+use bit_field::BitField;
+
+/// CMD23 (SET_BLOCK_COUNT) argument
+#[derive(Default)]
+pub struct Cmd23 {
+    pub val: u32,
+}
+
+impl Cmd23 {
+    pub fn set_block_count(&mut self, count: u16) -> &mut Self {
+        self.val.set_bits(0..16, count as u32);
+        self
+    }
+
+    pub fn block_count(&self) -> u16 {
+        self.val.get_bits(0..16) as u16
+    }
+
+    /// eMMC only. The following CMD25 is a reliable write
+    pub fn set_reliable_write(&mut self, reliable: bool) -> &mut Self {
+        self.val.set_bit(31, reliable);
+        self
+    }
+
+    pub fn reliable_write(&self) -> bool {
+        self.val.get_bit(31)
+    }
+}
+
+impl From<Cmd23> for u32 {
+    #[inline(always)]
+    fn from(val: Cmd23) -> Self {
+        val.val
+    }
+}
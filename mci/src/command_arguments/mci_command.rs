@@ -11,6 +11,10 @@ impl From<u32> for MciCommand {
 }
 
 impl MciCommand {
+    pub fn index(&self) -> u8 {
+        self.val.get_bits(0..6) as u8
+    }
+
     pub fn have_response(&self) -> bool {
         self.val.get_bit(8)
     }
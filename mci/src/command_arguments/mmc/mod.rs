@@ -95,6 +95,15 @@ impl Cmd6 {
         self.val.get_bits(8..=15) > 0
     }
 
+    pub fn set_value(&mut self, value: u8) -> &mut Self {
+        self.val.set_bits(8..=15, value as u32);
+        self
+    }
+
+    pub fn value(&self) -> u8 {
+        self.val.get_bits(8..=15) as u8
+    }
+
     pub fn set_cmd(&mut self, cmd: u8) -> &mut Self {
         self.val.set_bits(0..=2, cmd as u32);
         self
@@ -1,3 +1,4 @@
+pub mod cmd23;
 pub mod mci_command;
 pub mod mmc;
 pub mod sd;
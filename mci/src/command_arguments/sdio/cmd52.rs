@@ -35,13 +35,14 @@ impl Cmd52 {
         self.val.get_bits(0..=7) as u8
     }
 
+    /// Only the 17 bits of the address are kept
     pub fn set_register_address(&mut self, val: u32) -> &mut Self {
-        self.val.set_bits(9..25, val);
+        self.val.set_bits(9..=25, val.get_bits(0..17));
         self
     }
 
     pub fn register_address(&self) -> u32 {
-        self.val.get_bits(9..25)
+        self.val.get_bits(9..=25)
     }
 
     pub fn set_read_after_write(&mut self, enabled: bool) -> &mut Self {
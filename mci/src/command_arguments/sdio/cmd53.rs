@@ -28,6 +28,17 @@ impl From<bool> for OpCode {
     }
 }
 
+/// Amount of data moved by a CMD53
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransferCount {
+    /// Byte mode, 1 to 512 bytes
+    Bytes(u16),
+    /// Block mode, 1 to 511 blocks of the function's block size
+    Blocks(u16),
+    /// Block mode, the transfer goes on until it is aborted
+    InfiniteBlocks,
+}
+
 impl Cmd53 {
     pub fn set_block_or_bytes_count(&mut self, amount: u16) -> &mut Self {
         self.val.set_bits(0..=8, amount as u32);
@@ -38,13 +49,14 @@ impl Cmd53 {
         self.val.get_bits(0..=8) as u16
     }
 
-    pub fn set_address(&mut self, address: u16) -> &mut Self {
-        self.val.set_bits(9..=25, address as u32);
+    /// Only the 17 bits of the address are kept
+    pub fn set_address(&mut self, address: u32) -> &mut Self {
+        self.val.set_bits(9..=25, address.get_bits(0..17));
         self
     }
 
-    pub fn address(&self) -> u16 {
-        self.val.get_bits(9..=25) as u16
+    pub fn address(&self) -> u32 {
+        self.val.get_bits(9..=25)
     }
 
     pub fn set_op_code_increment_address(&mut self, op_code: OpCode) -> &mut Self {
@@ -65,6 +77,20 @@ impl Cmd53 {
         self.val.get_bit(27)
     }
 
+    /// Set the block mode and the byte or block count
+    /// A count of 512 bytes is encoded as 0, as is an infinite block count
+    pub fn set_transfer_count(&mut self, count: TransferCount) -> &mut Self {
+        match count {
+            TransferCount::Bytes(bytes) => self
+                .set_block_mode(false)
+                .set_block_or_bytes_count(bytes % 512),
+            TransferCount::Blocks(blocks) => {
+                self.set_block_mode(true).set_block_or_bytes_count(blocks)
+            }
+            TransferCount::InfiniteBlocks => self.set_block_mode(true).set_block_or_bytes_count(0),
+        }
+    }
+
     pub fn set_function_number(&mut self, function: u8) -> &mut Self {
         self.val.set_bits(28..=30, function as u32);
         self
@@ -83,3 +109,42 @@ impl Cmd53 {
         self.val.get_bit(31).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(count: TransferCount) -> (bool, u16) {
+        let mut arg = Cmd53::default();
+        arg.set_transfer_count(count);
+        (arg.block_mode(), arg.block_or_bytes_count())
+    }
+
+    #[test]
+    fn byte_counts_encode_512_as_0() {
+        assert_eq!(encoded(TransferCount::Bytes(1)), (false, 1));
+        assert_eq!(encoded(TransferCount::Bytes(511)), (false, 511));
+        assert_eq!(encoded(TransferCount::Bytes(512)), (false, 0));
+    }
+
+    #[test]
+    fn block_counts_use_block_mode() {
+        assert_eq!(encoded(TransferCount::Blocks(1)), (true, 1));
+        assert_eq!(encoded(TransferCount::Blocks(511)), (true, 511));
+        assert_eq!(encoded(TransferCount::InfiniteBlocks), (true, 0));
+    }
+
+    #[test]
+    fn transfer_count_keeps_the_other_fields() {
+        let mut arg = Cmd53::default();
+        arg.set_address(0x1_FFFF)
+            .set_function_number(7)
+            .set_transfer_count(TransferCount::Blocks(511));
+        assert_eq!(arg.address(), 0x1_FFFF);
+        assert_eq!(arg.function_number(), 7);
+        arg.set_transfer_count(TransferCount::Bytes(3));
+        assert!(!arg.block_mode());
+        assert_eq!(arg.block_or_bytes_count(), 3);
+        assert_eq!(arg.address(), 0x1_FFFF);
+    }
+}
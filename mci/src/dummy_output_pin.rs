@@ -0,0 +1,21 @@
+use embedded_hal::digital::v2::OutputPin;
+
+/// A dummy output pin.
+/// Can be used when a card power switch pin is not available for the peripheral
+pub struct DummyOutputPin {
+    pub high: bool,
+}
+
+impl OutputPin for DummyOutputPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.high = false;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.high = true;
+        Ok(())
+    }
+}
@@ -1,3 +1,11 @@
+use crate::command_arguments::mci_command::MciCommand;
+use crate::registers::sd::card_status::CardStatusRegister;
+#[cfg(feature = "sdio")]
+use crate::sdio_state::SdioR5State;
+use embedded_error::mci::{CommandOrDataError, MciError};
+#[cfg(feature = "sdio")]
+use embedded_error::ImplError;
+
 pub enum SdMmcError {
     InitOngoing = 1,
     NoCard = 2,
@@ -7,3 +15,163 @@ pub enum SdMmcError {
     IllegalParameter = 6,
     WriteProtected = 7,
 }
+
+/// Response of the command an error happened on, decoded by response type
+#[derive(Copy, Clone)]
+pub enum CommandResponse {
+    /// R1 or R1b card status
+    CardStatus(CardStatusRegister),
+    /// Response flags and data of an SDIO CMD52 or CMD53
+    #[cfg(feature = "sdio")]
+    Sdio(SdioR5State),
+    /// Any other short response
+    Raw(u32),
+}
+
+/// Command an error happened on
+#[derive(Copy, Clone)]
+pub struct CommandContext {
+    /// Command index, 0 to 63
+    pub index: u8,
+    /// Command argument
+    pub argument: u32,
+    /// Response of the card, None when the command failed before one was received
+    pub response: Option<CommandResponse>,
+}
+
+impl CommandContext {
+    /// # Arguments
+    /// * `command` Command as given to the Mci, its index is kept
+    /// * `argument` Command argument
+    /// * `response` Response of the card, if any
+    pub fn new(command: u32, argument: u32, response: Option<CommandResponse>) -> Self {
+        CommandContext {
+            index: MciCommand::from(command).index(),
+            argument,
+            response,
+        }
+    }
+
+    /// Raw short response
+    pub fn raw_response(&self) -> Option<u32> {
+        match self.response? {
+            CommandResponse::CardStatus(status) => Some(status.val),
+            #[cfg(feature = "sdio")]
+            CommandResponse::Sdio(state) => Some(state.val as u32),
+            CommandResponse::Raw(val) => Some(val),
+        }
+    }
+}
+
+/// The card was removed during a transfer
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CardRemoved {
+    /// Blocks transferred before the removal
+    pub blocks_completed: u32,
+}
+
+/// Error of a card command, with the command and the response of the card
+pub struct SdMmcCommandError {
+    /// What went wrong, the error the MciError API gives back
+    pub kind: MciError,
+    /// Command that failed, None when the error is not tied to a command
+    pub command: Option<CommandContext>,
+    /// Set when the card was removed during a transfer, the kind is then NoCard
+    pub removed: Option<CardRemoved>,
+}
+
+impl SdMmcCommandError {
+    /// # Arguments
+    /// * `kind` What went wrong
+    /// * `command` Command that failed
+    pub fn new(kind: MciError, command: CommandContext) -> Self {
+        SdMmcCommandError {
+            kind,
+            command: Some(command),
+            removed: None,
+        }
+    }
+
+    /// Card status of the failed command, for R1 and R1b responses
+    pub fn card_status(&self) -> Option<CardStatusRegister> {
+        match self.command?.response? {
+            CommandResponse::CardStatus(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    /// Response flags of the failed command, for SDIO R5 responses
+    #[cfg(feature = "sdio")]
+    pub fn sdio_state(&self) -> Option<SdioR5State> {
+        match self.command?.response? {
+            CommandResponse::Sdio(state) => Some(state),
+            _ => None,
+        }
+    }
+}
+
+impl From<MciError> for SdMmcCommandError {
+    fn from(kind: MciError) -> Self {
+        SdMmcCommandError {
+            kind,
+            command: None,
+            removed: None,
+        }
+    }
+}
+
+impl From<CardRemoved> for SdMmcCommandError {
+    fn from(removed: CardRemoved) -> Self {
+        SdMmcCommandError {
+            kind: MciError::NoCard,
+            command: None,
+            removed: Some(removed),
+        }
+    }
+}
+
+impl From<CardRemoved> for MciError {
+    fn from(_: CardRemoved) -> Self {
+        MciError::NoCard
+    }
+}
+
+impl From<SdMmcCommandError> for MciError {
+    fn from(err: SdMmcCommandError) -> Self {
+        err.kind
+    }
+}
+
+/// Error reported by an R1 card status, if any
+/// # Arguments
+/// * `status` Card status of the response
+/// * `data_error` Error of the data transfer, given back for address, ECC and card internal errors
+pub fn card_status_error(status: &CardStatusRegister, data_error: MciError) -> Option<MciError> {
+    if status.write_protect_violation() {
+        Some(MciError::WriteProtected)
+    } else if status.unlock_failed() {
+        Some(MciError::UnusableCard)
+    } else if status.communication_crc_error() {
+        Some(MciError::CommandError(CommandOrDataError::Crc))
+    } else if status.has_error() {
+        Some(data_error)
+    } else {
+        None
+    }
+}
+
+/// Error reported by SDIO R5 response flags, if any
+/// The CRC error flag is about the previous command, it is not an error of this one
+/// # Arguments
+/// * `state` Response flags
+/// * `data_error` Error of the transfer, given back for general errors
+#[cfg(feature = "sdio")]
+pub fn sdio_state_error(state: &SdioR5State, data_error: MciError) -> Option<MciError> {
+    if state.illegal_command() || state.invalid_function_number() || state.argument_out_of_range() {
+        Some(MciError::Impl(ImplError::InvalidConfiguration))
+    } else if state.general_error() {
+        Some(data_error)
+    } else {
+        None
+    }
+}
@@ -1,3 +1,4 @@
+use crate::card_state::CardState;
 use crate::card_version::CardVersion::{Mmc, Unknown};
 use crate::card_version::MmcVersion;
 use crate::command_arguments::mmc::{Access, BusWidth, Cmd6};
@@ -6,56 +7,75 @@ use crate::commands::{
     SDMMC_CMD16_SET_BLOCKLEN, SDMMC_CMD2_ALL_SEND_CID, SDMMC_CMD7_SELECT_CARD_CMD,
     SDMMC_MCI_CMD0_GO_IDLE_STATE,
 };
+use crate::error::{CommandContext, CommandResponse};
+use crate::functions::sd_mmc_timeout::SD_MMC_COMMAND_POLL_CYCLES;
 use crate::functions::sdmmc::SD_MMC_BLOCK_SIZE;
 use crate::mci::Mci;
 use crate::mci_card::{ocr_voltage_support, MciCard, MMC_TRANS_MULTIPLIERS, SD_MMC_TRANS_UNITS};
 use crate::mode_index::ModeIndex;
 use crate::registers::ocr::{AccessMode, OcrRegister};
 use crate::registers::sd::card_status::CardStatusRegister;
+use crate::timeout::{MonotonicClock, SD_MMC_INIT_TIMEOUT_US};
+use crate::transfer::TransferTransaction;
 use bit_field::BitField;
 use embedded_error::mci::MciError;
 use embedded_error::mci::SetupError;
 use embedded_error::ImplError;
-use embedded_hal::digital::v2::InputPin;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
 
+pub const EXT_CSD_WR_REL_PARAM_INDEX: u32 = 166;
 pub const EXT_CSD_CARD_TYPE_INDEX: u32 = 196;
 pub const EXT_CSD_SEC_COUNT_INDEX: u32 = 212;
+pub const EXT_CSD_REL_WR_SEC_C_INDEX: u32 = 222;
 pub const EXT_CSD_BSIZE: u32 = 512;
+/// POWER_OFF_NOTIFICATION values
+pub const MMC_POWERED_ON: u8 = 1;
+pub const MMC_POWER_OFF_SHORT: u8 = 2;
+pub const MMC_POWER_OFF_LONG: u8 = 3;
 
-impl<MCI, WP, DETECT> MciCard<MCI, WP, DETECT>
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
 where
     MCI: Mci,
     WP: InputPin,     // Write protect pin
     DETECT: InputPin, // Card detect pin
+    POWER: OutputPin, // Card power switch pin
+    CLOCK: MonotonicClock,
 {
     /// Sends operation condition command and read OCR (MCI only)
     pub fn mmc_mci_send_operation_condition(&mut self) -> Result<(), MciError> {
-        let mut ocr = ocr_voltage_support();
-        ocr.set_access_mode(AccessMode::Sector);
-        // Timeout is 1s = 400KHz / ((6+6)*8) cycles = 4200 retries. TODO maybe a delay check?
-        for i in (0..4200).rev() {
-            if i == 0 {
+        let mut timeout = self.sd_mmc_timeout(SD_MMC_INIT_TIMEOUT_US, SD_MMC_COMMAND_POLL_CYCLES);
+        while !self.mmc_cmd1_poll()? {
+            if timeout.expired(self.monotonic_clock.as_ref()) {
                 return Err(MciError::Impl(ImplError::TimedOut));
             }
-            self.mci
-                .send_command(MMC_MCI_CMD1_SEND_OP_COND.into(), ocr.val)?;
-            let response = self.mci.get_response()?;
-            let response = OcrRegister { val: response };
-            if response.card_powered_up_status() {
-                if response.access_mode() == AccessMode::Sector {
-                    self.card_type.set_high_capacity(true);
-                }
-                break;
-            }
         }
         Ok(())
     }
 
+    /// One CMD1 with the voltage window and sector access mode
+    /// self.card_type is updated once the card is powered up
+    /// Returns whether the card is powered up
+    pub(crate) fn mmc_cmd1_poll(&mut self) -> Result<bool, MciError> {
+        let mut ocr = ocr_voltage_support();
+        ocr.set_access_mode(AccessMode::Sector);
+        self.mci
+            .send_command(MMC_MCI_CMD1_SEND_OP_COND.into(), ocr.val)?;
+        let response = self.mci.get_response()?;
+        let response = OcrRegister { val: response };
+        if response.card_powered_up_status() {
+            self.card_type.set_mmc(true);
+            if response.access_mode() == Some(AccessMode::Sector) {
+                self.card_type.set_high_capacity(true);
+            }
+        }
+        Ok(response.card_powered_up_status())
+    }
+
     /// CMD6 for MMC - Switches the bus width mode
     pub fn mmc_cmd6_set_bus_width(&mut self, bus_width: &BusWidth) -> Result<bool, MciError> {
         let mut arg = Cmd6::default();
         arg.set_access(Access::SetBits)
-            .set_bus_width(&bus_width)
+            .set_bus_width(bus_width)
             .set_mode_index(ModeIndex::BusWidth);
         self.mci.send_command(MMC_CMD6_SWITCH.into(), arg.val)?;
         let ret = CardStatusRegister {
@@ -65,7 +85,7 @@ where
             // Not supported, not a protocol error
             return Ok(false);
         }
-        self.bus_width = bus_width.clone();
+        self.bus_width = *bus_width;
         Ok(true)
     }
 
@@ -90,39 +110,144 @@ where
         Ok(true)
     }
 
+    /// CMD6 for MMC - Tells the card it is about to be powered off (POWER_OFF_NOTIFICATION)
+    /// The notification is enabled (POWERED_ON) first, then the card is notified and finishes its
+    /// internal operations
+    /// Returns false when the card does not support it
+    /// # Arguments
+    /// * `long` POWER_OFF_LONG, the card may take longer to get ready. Otherwise POWER_OFF_SHORT
+    pub fn mmc_power_off_notification(&mut self, long: bool) -> Result<bool, MciError> {
+        let power_off = if long {
+            MMC_POWER_OFF_LONG
+        } else {
+            MMC_POWER_OFF_SHORT
+        };
+        for value in [MMC_POWERED_ON, power_off] {
+            let mut arg = Cmd6::default();
+            arg.set_access(Access::WriteByte)
+                .set_mode_index(ModeIndex::PowerOffNotification)
+                .set_value(value);
+            let ret = match self
+                .mci
+                .send_command(MMC_CMD6_SWITCH.into(), arg.val)
+                .and_then(|_| self.mci.get_response())
+            {
+                Ok(val) => CardStatusRegister { val },
+                Err(err) => {
+                    let context = CommandContext::new(MMC_CMD6_SWITCH.into(), arg.val, None);
+                    return Err(self.sd_mmc_command_error(err, context).into());
+                }
+            };
+            if ret.switch_error() {
+                // Not supported, not a protocol error
+                return Ok(false);
+            }
+            // The card is busy until it is done
+            if let Err(err) = self.sd_mmc_cmd13_get_status_and_wait_for_ready_for_data_flag() {
+                let context = CommandContext::new(
+                    MMC_CMD6_SWITCH.into(),
+                    arg.val,
+                    Some(CommandResponse::CardStatus(ret)),
+                );
+                return Err(self.sd_mmc_command_error(err, context).into());
+            }
+        }
+        Ok(true)
+    }
+
+    /// Notify the card of the power off, then power cycle it and install it again
+    /// The notification is best effort: a card that misses it is powered off all the same, and the
+    /// failed command is kept in self.last_command_error
+    /// A card that cannot be power cycled (sd_mmc_can_power_cycle) is not notified, and the error
+    /// is InvalidConfiguration. A card that was notified but could not be powered off takes no
+    /// more commands, it is marked Unusable
+    /// # Arguments
+    /// * `long` Send POWER_OFF_LONG instead of POWER_OFF_SHORT
+    pub fn mmc_power_cycle(&mut self, long: bool) -> Result<(), MciError> {
+        if !self.sd_mmc_can_power_cycle() {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        let _ = self.mmc_power_off_notification(long);
+        if let Err(err) = self.sd_mmc_power_off() {
+            self.state = CardState::Unusable;
+            return Err(err);
+        }
+        // The install powers the card on again
+        self.card_type.set_unknown();
+        self.sd_mmc_mci_install_mmc()
+    }
+
     /// CMD8 - The card sends its EXT_CSD as a block of data
     /// Returns whether high speed can be handled by this
-    /// self.capacity is updated
+    /// self.capacity, self.reliable_write_supported, self.reliable_write_sector_count and
+    /// self.enhanced_reliable_write are updated
     pub fn mmc_cmd8_high_speed_capable_and_update_capacity(&mut self) -> Result<bool, MciError> {
         self.mci
             .adtc_start(MMC_CMD8_SEND_EXT_CSD.into(), 0, 512, 1, false)?;
 
-        let mut index = 0u32;
-        let mut read = (0u32, 0u8);
-        // Read in bytes (4 at a time) and not to a buffer to "fast forward" to the card type
-        while index < ((EXT_CSD_CARD_TYPE_INDEX + 4) / 4) {
-            read = self.mci.read_word()?;
-            index += 1;
-        }
-        let high_speed_capable =
-            (read.0 >> ((EXT_CSD_CARD_TYPE_INDEX % 4) * 8)).get_bits(0..2) == 0x2; // 52MHz = 0x2, 26MHz = 0x1
-
-        if self.csd.card_size() == 0xFFF {
-            // For high capacity SD/MMC card, memory capacity = sec_count * 512 bytes
-            while index < (EXT_CSD_SEC_COUNT_INDEX + 4) / 4 {
-                read = self.mci.read_word()?;
-                index += 1;
+        let mut high_speed_capable = false;
+        // Read in words (4 bytes at a time) and not to a buffer, picking out the fields needed
+        for index in 0..EXT_CSD_BSIZE / 4 {
+            let (word, _) = self.mci.read_word()?;
+            let byte = |field: u32| (word >> ((field % 4) * 8)).get_bits(0..8) as u8;
+            if index == EXT_CSD_WR_REL_PARAM_INDEX / 4 {
+                self.enhanced_reliable_write = byte(EXT_CSD_WR_REL_PARAM_INDEX).get_bit(2);
+            }
+            if index == EXT_CSD_CARD_TYPE_INDEX / 4 {
+                // 52MHz = 0x2, 26MHz = 0x1
+                high_speed_capable = byte(EXT_CSD_CARD_TYPE_INDEX).get_bits(0..2) == 0x2;
+            }
+            if index == EXT_CSD_SEC_COUNT_INDEX / 4 && self.csd.card_size() == 0xFFF {
+                // For high capacity SD/MMC card, memory capacity = sec_count * 512 bytes
+                self.capacity = word;
+            }
+            if index == EXT_CSD_REL_WR_SEC_C_INDEX / 4 {
+                self.reliable_write_sector_count = byte(EXT_CSD_REL_WR_SEC_C_INDEX);
             }
-            self.capacity = read.0
-        }
-        // Forward to the end
-        while index < EXT_CSD_BSIZE / 4 {
-            self.mci.read_word()?;
-            index += 1;
         }
+        self.reliable_write_supported = self.reliable_write_sector_count > 0;
+        self.mci.wait_until_read_finished()?;
         Ok(high_speed_capable)
     }
 
+    /// Start a reliable write (CMD23 with the reliable write flag followed by CMD25)
+    /// Reliable writes guarantee that the old data of a sector is kept on power loss up until the
+    /// new data is written
+    /// Without enhanced reliable write (EN_REL_WR) the card only takes a single block, or
+    /// REL_WR_SEC_C blocks starting at a multiple of REL_WR_SEC_C. Split longer writes with
+    /// mmc_reliable_write_blocks_amount
+    pub fn mmc_init_reliable_write_blocks(
+        &mut self,
+        start: u32,
+        blocks_amount: u16,
+    ) -> Result<TransferTransaction, MciError> {
+        if !self.set_block_count_supported || !self.reliable_write_supported {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        if blocks_amount == 0
+            || self.mmc_reliable_write_blocks_amount(start, blocks_amount) != blocks_amount
+        {
+            return Err(MciError::IncorrectDataSize);
+        }
+        self.sd_mmc_init_write_blocks_with_reliability(start, blocks_amount, true)
+    }
+
+    /// Amount of blocks, at most blocks_amount, the next reliable write starting at start can take
+    /// # Arguments
+    /// * `start` Start block of the write
+    /// * `blocks_amount` Blocks left to write
+    pub fn mmc_reliable_write_blocks_amount(&self, start: u32, blocks_amount: u16) -> u16 {
+        if self.enhanced_reliable_write {
+            return blocks_amount;
+        }
+        let sector_count = self.reliable_write_sector_count as u16;
+        if sector_count > 1 && start % sector_count as u32 == 0 && blocks_amount >= sector_count {
+            sector_count
+        } else {
+            blocks_amount.min(1)
+        }
+    }
+
     /// Decode CSD for MMC
     /// Updates self.version, self.clock, self.capacity
     pub fn mmc_decode_csd(&mut self) -> Result<(), MciError> {
@@ -166,11 +291,17 @@ where
     /// sets the SD/MMC card in transfer state.
     /// At last, it will enable maximum bus width and transfer speed.
     pub fn sd_mmc_mci_install_mmc(&mut self) -> Result<(), MciError> {
+        self.sd_mmc_power_on()?;
         // CMD0 - Reset all cards to idle state.
         self.mci
             .send_command(SDMMC_MCI_CMD0_GO_IDLE_STATE.into(), 0)?;
         self.mmc_mci_send_operation_condition()?;
+        self.mmc_mci_install_powered_up()
+    }
 
+    /// Identify a powered up MMC card, then set it in transfer state with the maximum bus width
+    /// and transfer speed
+    pub(crate) fn mmc_mci_install_powered_up(&mut self) -> Result<(), MciError> {
         // Put the card in Identify Mode
         // Note: The CID is not used
         self.mci.send_command(SDMMC_CMD2_ALL_SEND_CID.into(), 0)?;
@@ -189,6 +320,8 @@ where
             .send_command(SDMMC_CMD7_SELECT_CARD_CMD.into(), (self.rca as u32) << 16)?;
 
         let version: usize = self.version.into();
+        // CMD23 is mandatory from MMC 3.1
+        self.set_block_count_supported = version >= MmcVersion::SdMmc3d0 as usize;
         if version >= MmcVersion::Mmc4d0 as usize {
             // For MMC 4.0 Higher version
             // Get EXT_CSD
@@ -214,17 +347,11 @@ where
         } else {
             self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
         }
-        for _ in 0..10 {
-            // Retry is a workaround for no compliance card (Atmel Internal ref. MMC19)
-            // These cards seem not ready immediately after the end of busy of mmc_cmd6_set_high_speed
-            if self
-                .mci
+        // Retry is a workaround for no compliance card (Atmel Internal ref. MMC19)
+        // These cards seem not ready immediately after the end of busy of mmc_cmd6_set_high_speed
+        self.sd_mmc_with_command_retry(|card| {
+            card.mci
                 .send_command(SDMMC_CMD16_SET_BLOCKLEN.into(), SD_MMC_BLOCK_SIZE)
-                .is_ok()
-            {
-                return Ok(());
-            }
-        }
-        Err(MciError::Impl(ImplError::TimedOut))
+        })
     }
 }
@@ -1,6 +1,37 @@
 #[cfg(feature = "mmc")]
 pub mod mmc;
 pub mod sd;
+#[cfg(feature = "async")]
+pub mod sd_mmc_async;
+pub mod sd_mmc_error;
+pub mod sd_mmc_hot_plug;
+pub mod sd_mmc_power;
+pub mod sd_mmc_recovery;
+pub mod sd_mmc_removal;
+pub mod sd_mmc_timeout;
+pub mod sd_mmc_transfer_machine;
 #[cfg(feature = "sdio")]
 pub mod sdio;
+#[cfg(feature = "sdio")]
+pub mod sdio_card_info;
+#[cfg(feature = "sdio")]
+pub mod sdio_cis;
+#[cfg(feature = "sdio")]
+pub mod sdio_combo;
+#[cfg(feature = "sdio")]
+pub mod sdio_csa;
+#[cfg(feature = "sdio")]
+pub mod sdio_driver;
+#[cfg(feature = "sdio")]
+pub mod sdio_function;
+#[cfg(feature = "sdio")]
+pub mod sdio_interrupt;
+#[cfg(feature = "sdio")]
+pub mod sdio_power;
+#[cfg(feature = "sdio")]
+pub mod sdio_recovery;
+#[cfg(feature = "sdio")]
+pub mod sdio_register;
+#[cfg(feature = "sdio")]
+pub mod sdio_suspend;
 mod sdmmc;
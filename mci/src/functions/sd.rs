@@ -6,9 +6,13 @@ use crate::command_arguments::sd::cmd8::Cmd8;
 use crate::command_flags::CommandFlag;
 use crate::command_responses::Response;
 use crate::commands::{
-    Command, SDMMC_CMD55_APP_CMD, SD_ACMD51_SEND_SCR, SD_ACMD6_SET_BUS_WIDTH, SD_CMD6_SWITCH_FUNC,
-    SD_CMD8_SEND_IF_COND, SD_MCI_ACMD41_SD_SEND_OP_COND,
+    Command, SDMMC_CMD16_SET_BLOCKLEN, SDMMC_CMD2_ALL_SEND_CID, SDMMC_CMD55_APP_CMD,
+    SDMMC_CMD7_SELECT_CARD_CMD, SDMMC_MCI_CMD0_GO_IDLE_STATE, SD_ACMD51_SEND_SCR,
+    SD_ACMD6_SET_BUS_WIDTH, SD_CMD3_SEND_RELATIVE_ADDR, SD_CMD6_SWITCH_FUNC, SD_CMD8_SEND_IF_COND,
+    SD_MCI_ACMD41_SD_SEND_OP_COND,
 };
+use crate::functions::sd_mmc_timeout::SD_ACMD41_POLL_CYCLES;
+use crate::functions::sdmmc::SD_MMC_BLOCK_SIZE;
 use crate::mci::Mci;
 use crate::mci_card::{ocr_voltage_support, MciCard, SD_MMC_TRANS_UNITS, SD_TRANS_MULTIPLIERS};
 use crate::registers::csd::SdCsdStructureVersion;
@@ -16,46 +20,111 @@ use crate::registers::ocr::OcrRegister;
 use crate::registers::sd::scr::ScrRegister;
 use crate::registers::sd::switch_status::{SwitchStatusRegister, SD_SW_STATUS_FUN_GRP_RC_ERROR};
 use crate::sd::sd_physical_specification::SdPhysicalSpecification;
+use crate::timeout::{MonotonicClock, SD_MMC_INIT_TIMEOUT_US};
 use bit_field::BitField;
 use embedded_error::mci::MciError;
 use embedded_error::ImplError;
-use embedded_hal::digital::v2::InputPin;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
 
-impl<MCI, WP, DETECT> MciCard<MCI, WP, DETECT>
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
 where
     MCI: Mci,
     WP: InputPin,
     DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
 {
+    /// Initialize the SD memory card in MCI mode
+    /// This function runs the initialization procedure and the identification process, then it
+    /// sets the SD card in transfer state.
+    /// At last, it will enable maximum bus width and transfer speed.
+    pub fn sd_mmc_mci_install_sd(&mut self) -> Result<(), MciError> {
+        self.card_type.set_unknown();
+        self.sd_mmc_power_on()?;
+        // CMD0 - Reset all cards to idle state.
+        self.mci
+            .send_command(SDMMC_MCI_CMD0_GO_IDLE_STATE.into(), 0)?;
+        // CMD8 must come before the first ACMD41 of a v2 card
+        let v2 = self.sd_cmd8_is_v2()?;
+        self.sd_mci_operations_conditions(v2)?;
+        self.card_type.set_sd(true);
+        self.sd_mci_install_powered_up()
+    }
+
+    /// Identify a powered up SD memory card, then set it in transfer state with the maximum bus
+    /// width and transfer speed
+    fn sd_mci_install_powered_up(&mut self) -> Result<(), MciError> {
+        // Put the card in Identify Mode
+        // Note: The CID is not used
+        self.mci.send_command(SDMMC_CMD2_ALL_SEND_CID.into(), 0)?;
+
+        // Ask the card to publish a new relative address
+        self.mci
+            .send_command(SD_CMD3_SEND_RELATIVE_ADDR.into(), 0)?;
+        self.rca = (self.mci.get_response()? >> 16) as u16;
+
+        // Get the card specific data
+        self.sd_mmc_cmd9_mci()?;
+        self.sd_decode_csd()?;
+
+        // Select the card and put it into Transfer mode
+        self.mci
+            .send_command(SDMMC_CMD7_SELECT_CARD_CMD.into(), (self.rca as u32) << 16)?;
+
+        // Get the SD version and CMD23 support
+        self.sd_acmd51()?;
+        self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+
+        if BusWidth::_4BIT <= self.mci.get_bus_width(self.slot)? {
+            self.sd_acmd6_set_data_bus_width_to_4_bits()?;
+            self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        }
+
+        // CMD6 is only supported from SD 1.10
+        if self.mci.is_high_speed_capable()?
+            && usize::from(self.version) > SdCardVersion::Sd1d0 as usize
+            && self.sd_cmd6_set_to_high_speed_mode()?
+        {
+            self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        }
+
+        self.mci
+            .send_command(SDMMC_CMD16_SET_BLOCKLEN.into(), SD_MMC_BLOCK_SIZE)?;
+        Ok(())
+    }
+
     /// Ask all cards to send their operations conditions (MCI only).
     /// # Arguments
     /// * `v2` Shall be true if it is a SD card V2
     pub fn sd_mci_operations_conditions(&mut self, v2: bool) -> Result<(), MciError> {
-        // Timeout 1s = 400KHz / ((6+6+6+6)*8) cycles = 2100 retry
-        for i in (0..2100).rev() {
-            if i == 0 {
+        let mut timeout = self.sd_mmc_timeout(SD_MMC_INIT_TIMEOUT_US, SD_ACMD41_POLL_CYCLES);
+        while !self.sd_acmd41_poll(v2)? {
+            if timeout.expired(self.monotonic_clock.as_ref()) {
                 return Err(MciError::Impl(ImplError::TimedOut));
             }
-            // CMD55 - Indicate to the card that the next command is an
-            // application specific command rather than a standard command.
-
-            self.mci.send_command(SDMMC_CMD55_APP_CMD.into(), 0)?;
-            let mut arg = ocr_voltage_support();
-            arg.val.set_bit(30, v2); // SD_ACMD41_HCS ACMD41 High Capacity Support
-            self.mci
-                .send_command(SD_MCI_ACMD41_SD_SEND_OP_COND.into(), arg.val)?;
-            let resp = self.mci.get_response()?;
-            let resp = OcrRegister { val: resp };
-            if resp.card_powered_up_status() {
-                if resp.card_capacity_status() {
-                    self.card_type.set_high_capacity(true);
-                }
-                break;
-            }
         }
         Ok(())
     }
 
+    /// One ACMD41 with the voltage window
+    /// self.card_type is updated once the card is powered up
+    /// Returns whether the card is powered up
+    pub(crate) fn sd_acmd41_poll(&mut self, v2: bool) -> Result<bool, MciError> {
+        // CMD55 - Indicate to the card that the next command is an
+        // application specific command rather than a standard command.
+        self.mci.send_command(SDMMC_CMD55_APP_CMD.into(), 0)?;
+        let mut arg = ocr_voltage_support();
+        arg.val.set_bit(30, v2); // SD_ACMD41_HCS ACMD41 High Capacity Support
+        self.mci
+            .send_command(SD_MCI_ACMD41_SD_SEND_OP_COND.into(), arg.val)?;
+        let resp = self.mci.get_response()?;
+        let resp = OcrRegister { val: resp };
+        if resp.card_powered_up_status() && resp.card_capacity_status() {
+            self.card_type.set_high_capacity(true);
+        }
+        Ok(resp.card_powered_up_status())
+    }
+
     pub fn sd_cmd6<RESPONSE: Response, FLAG: CommandFlag>(
         &mut self,
         command: Command<RESPONSE, FLAG>,
@@ -102,7 +171,7 @@ where
         self.high_speed = true;
         self.clock *= 2;
 
-        Ok(false)
+        Ok(true)
     }
 
     /// CMD8 for SD card - send interface condition command
@@ -177,13 +246,14 @@ where
     /// ACMD51 - Read the SD Card configuration register (SCR)
     /// SCR provides information on the SD Memory Card's special features that were configured
     /// into the given card. The SCR register is 64 bits.
-    /// Updates self.version
+    /// Updates self.version, self.set_block_count_supported
     pub fn sd_acmd51(&mut self) -> Result<(), MciError> {
         let scr = self.sd_scr()?;
+        self.set_block_count_supported = scr.supports_set_block_count();
         self.version = match scr.sd_specification_version() {
-            SdPhysicalSpecification::Revision1d01 => SdCard(SdCardVersion::Sd1d0),
-            SdPhysicalSpecification::Revision1d10 => SdCard(SdCardVersion::Sd1d10),
-            SdPhysicalSpecification::Revision2d00 => SdCard(SdCardVersion::Sd2d0),
+            Some(SdPhysicalSpecification::Revision1d01) => SdCard(SdCardVersion::Sd1d0),
+            Some(SdPhysicalSpecification::Revision1d10) => SdCard(SdCardVersion::Sd1d10),
+            Some(SdPhysicalSpecification::Revision2d00) => SdCard(SdCardVersion::Sd2d0),
             _ => SdCard(SdCardVersion::Sd1d0),
         };
         Ok(())
@@ -0,0 +1,310 @@
+use crate::async_mci::{yield_now, AsyncMci};
+#[cfg(feature = "sdio")]
+use crate::card_state::CardState;
+#[cfg(feature = "mmc")]
+use crate::commands::SDMMC_MCI_CMD0_GO_IDLE_STATE;
+use crate::commands::{
+    MMC_CMD23_SET_BLOCK_COUNT, SDMMC_CMD12_STOP_TRANSMISSION, SDMMC_MCI_CMD13_SEND_STATUS,
+};
+use crate::error::SdMmcCommandError;
+use crate::functions::sd_mmc_timeout::SD_MMC_COMMAND_POLL_CYCLES;
+#[cfg(feature = "sdio")]
+use crate::functions::sd_mmc_timeout::{SDIO_CMD5_POLL_CYCLES, SD_ACMD41_POLL_CYCLES};
+use crate::functions::sdmmc::{BlockCommand, SD_MMC_BLOCK_SIZE};
+use crate::mci_card::MciCard;
+use crate::recovery::CommandAttempts;
+use crate::registers::sd::card_status::CardStatusRegister;
+use crate::timeout::MonotonicClock;
+#[cfg(any(feature = "sdio", feature = "mmc"))]
+use crate::timeout::SD_MMC_INIT_TIMEOUT_US;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Most blocks a single read or write command transfers
+const SD_MMC_MAX_BLOCKS_PER_COMMAND: usize = u16::MAX as usize;
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: AsyncMci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// CMD13: Get status register, yielding until the card is ready for data
+    pub async fn sd_mmc_cmd13_wait_for_ready_for_data_async(
+        &mut self,
+    ) -> Result<CardStatusRegister, MciError> {
+        let mut timeout =
+            self.sd_mmc_timeout(self.sd_mmc_write_timeout_us(), SD_MMC_COMMAND_POLL_CYCLES);
+        loop {
+            // A card removed while it is programming never gets ready
+            self.sd_mmc_check_card_present()?;
+            if let Err(err) = self
+                .mci
+                .send_command_async(SDMMC_MCI_CMD13_SEND_STATUS.into(), (self.rca as u32) << 16)
+                .await
+            {
+                return Err(self.sd_mmc_check_removal(err));
+            }
+            let status = CardStatusRegister {
+                val: self.mci.get_response()?,
+            };
+            if status.ready_for_data() {
+                return Ok(status);
+            }
+            if timeout.expired(self.monotonic_clock.as_ref()) {
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
+            yield_now().await;
+        }
+    }
+
+    /// Read blocks of the memory
+    /// A removed card gives back CardRemoved, with the blocks of the commands that completed
+    /// # Arguments
+    /// * `start` First block
+    /// * `destination` Buffer to fill, a multiple of the block size
+    pub async fn sd_mmc_read_blocks_async(
+        &mut self,
+        start: u32,
+        destination: &mut [u8],
+    ) -> Result<(), SdMmcCommandError> {
+        if destination.len() % SD_MMC_BLOCK_SIZE as usize != 0 {
+            return Err(MciError::IncorrectDataSize.into());
+        }
+        let mut blocks_completed = 0u32;
+        for chunk in
+            destination.chunks_mut(SD_MMC_MAX_BLOCKS_PER_COMMAND * SD_MMC_BLOCK_SIZE as usize)
+        {
+            let blocks_amount = (chunk.len() / SD_MMC_BLOCK_SIZE as usize) as u16;
+            if let Err(err) = self
+                .sd_mmc_read_command_async(start + blocks_completed, chunk, blocks_amount)
+                .await
+            {
+                return Err(self.sd_mmc_transfer_error(err, blocks_completed));
+            }
+            blocks_completed += blocks_amount as u32;
+        }
+        Ok(())
+    }
+
+    /// Write blocks of the memory
+    /// A removed card gives back CardRemoved, with the blocks of the commands that completed
+    /// # Arguments
+    /// * `start` First block
+    /// * `data` Data to write, a multiple of the block size
+    pub async fn sd_mmc_write_blocks_async(
+        &mut self,
+        start: u32,
+        data: &[u8],
+    ) -> Result<(), SdMmcCommandError> {
+        if data.len() % SD_MMC_BLOCK_SIZE as usize != 0 {
+            return Err(MciError::IncorrectDataSize.into());
+        }
+        let mut blocks_completed = 0u32;
+        for chunk in data.chunks(SD_MMC_MAX_BLOCKS_PER_COMMAND * SD_MMC_BLOCK_SIZE as usize) {
+            let blocks_amount = (chunk.len() / SD_MMC_BLOCK_SIZE as usize) as u16;
+            if let Err(err) = self
+                .sd_mmc_write_command_async(start + blocks_completed, chunk, blocks_amount)
+                .await
+            {
+                return Err(self.sd_mmc_transfer_error(err, blocks_completed));
+            }
+            blocks_completed += blocks_amount as u32;
+        }
+        Ok(())
+    }
+
+    async fn sd_mmc_read_command_async(
+        &mut self,
+        start: u32,
+        destination: &mut [u8],
+        blocks_amount: u16,
+    ) -> Result<(), MciError> {
+        self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        self.sd_mmc_cmd13_wait_for_ready_for_data_async().await?;
+        let command = self.sd_mmc_block_command(false, start, blocks_amount, false);
+        let started = self.sd_mmc_adtc_start_async(&command).await;
+        self.sd_mmc_check_block_command(&command, started)?;
+
+        if self
+            .mci
+            .read_blocks_async(destination, blocks_amount)
+            .await
+            .is_err()
+        {
+            return Err(MciError::ReadError);
+        }
+        self.mci.wait_until_read_finished_async().await?;
+
+        if command.transaction().needs_stop(false) {
+            // Retried for no compliance card (Atmel Internal ref. !MMC7 !SD19)
+            self.sd_mmc_stop_transmission_async().await?;
+        }
+        Ok(())
+    }
+
+    async fn sd_mmc_write_command_async(
+        &mut self,
+        start: u32,
+        data: &[u8],
+        blocks_amount: u16,
+    ) -> Result<(), MciError> {
+        self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        if self.write_protected()? {
+            return Err(MciError::WriteProtected);
+        }
+        // The card may still be programming the previous write
+        self.sd_mmc_cmd13_wait_for_ready_for_data_async().await?;
+        let command = self.sd_mmc_block_command(true, start, blocks_amount, false);
+        let started = self.sd_mmc_adtc_start_async(&command).await;
+        self.sd_mmc_check_block_command(&command, started)?;
+
+        if self
+            .mci
+            .write_blocks_async(data, blocks_amount)
+            .await
+            .is_err()
+        {
+            return Err(MciError::WriteError);
+        }
+        self.mci.wait_until_write_finished_async().await?;
+
+        if command.transaction().needs_stop(false) {
+            self.sd_mmc_stop_transmission_async().await?;
+        }
+        Ok(())
+    }
+
+    /// CMD12: Stop the ongoing multi block transfer, retried on errors like
+    /// sd_mmc_stop_transmission
+    async fn sd_mmc_stop_transmission_async(&mut self) -> Result<(), MciError> {
+        let mut attempts = CommandAttempts::new(&self.recovery_policy);
+        loop {
+            match self
+                .mci
+                .adtc_stop_async(SDMMC_CMD12_STOP_TRANSMISSION.into(), 0)
+                .await
+            {
+                Err(err) if attempts.retry(&err) => {}
+                result => return result,
+            }
+        }
+    }
+
+    /// Start the ADTC of a block command, see sd_mmc_adtc_start_block_command
+    async fn sd_mmc_adtc_start_async(&mut self, command: &BlockCommand) -> Result<(), MciError> {
+        if command.block_count_set {
+            if self.mci.is_auto_cmd23_capable()? {
+                return self
+                    .mci
+                    .adtc_start_with_auto_cmd23_async(
+                        command.cmd,
+                        command.arg,
+                        SD_MMC_BLOCK_SIZE as u16,
+                        command.blocks_amount,
+                        true,
+                        command.cmd23(),
+                    )
+                    .await;
+            }
+            self.mci
+                .send_command_async(MMC_CMD23_SET_BLOCK_COUNT.into(), command.cmd23())
+                .await?;
+        }
+        self.mci
+            .adtc_start_async(
+                command.cmd,
+                command.arg,
+                SD_MMC_BLOCK_SIZE as u16,
+                command.blocks_amount,
+                true,
+            )
+            .await
+    }
+
+    /// Power on sequence, see sd_mmc_power_on
+    /// Yields to other tasks while the supply ramps up
+    pub async fn sd_mmc_power_on_async(&mut self) -> Result<(), MciError> {
+        self.sd_mmc_set_power(true)?;
+        if self.power.is_some() && self.power_ramp_up_us != 0 {
+            let clock = self
+                .monotonic_clock
+                .as_ref()
+                .ok_or(MciError::Impl(ImplError::InvalidConfiguration))?;
+            let start = clock.now_us();
+            while clock.now_us().wrapping_sub(start) < self.power_ramp_up_us {
+                yield_now().await;
+            }
+        }
+        self.sd_mmc_power_on_clocks()
+    }
+
+    /// Initialize an SDIO card, with the memory of a combo card, see sdio_install_combo
+    /// The operating condition polling yields to other tasks while the card powers up
+    #[cfg(feature = "sdio")]
+    pub async fn sdio_install_combo_async(&mut self) -> Result<(), MciError> {
+        self.state = CardState::Init;
+        let result = self.sdio_install_combo_card_async().await;
+        self.state = if result.is_ok() {
+            CardState::Ready
+        } else {
+            CardState::Unusable
+        };
+        result
+    }
+
+    #[cfg(feature = "sdio")]
+    async fn sdio_install_combo_card_async(&mut self) -> Result<(), MciError> {
+        self.card_type.set_unknown();
+        self.sdio_reset_install_state();
+        // 1-bit bus width and low clock for initialization
+        self.sd_mmc_power_on_async().await?;
+        let v2 = self.sdio_combo_reset_powered()?;
+        // CMD5 - Negotiate the IO operating voltage, and tell if there is a memory
+        if let Some(arg) = self.sdio_cmd5_probe()? {
+            let mut timeout = self.sd_mmc_timeout(SD_MMC_INIT_TIMEOUT_US, SDIO_CMD5_POLL_CYCLES);
+            while !self.sdio_cmd5_poll(arg)? {
+                if timeout.expired(self.monotonic_clock.as_ref()) {
+                    return Err(MciError::Impl(ImplError::TimedOut));
+                }
+                yield_now().await;
+            }
+        }
+        if !self.card_type.sdio() {
+            return Err(MciError::UnusableCard);
+        }
+        if self.card_type.sd() {
+            // ACMD41 - The memory gets the same voltage window as the IO
+            let mut timeout = self.sd_mmc_timeout(SD_MMC_INIT_TIMEOUT_US, SD_ACMD41_POLL_CYCLES);
+            while !self.sd_acmd41_poll(v2)? {
+                if timeout.expired(self.monotonic_clock.as_ref()) {
+                    return Err(MciError::Impl(ImplError::TimedOut));
+                }
+                yield_now().await;
+            }
+        }
+        self.sdio_combo_install_powered_up()
+    }
+
+    /// Initialize the MMC card in MCI mode, see sd_mmc_mci_install_mmc
+    /// The operating condition polling yields to other tasks while the card powers up
+    #[cfg(feature = "mmc")]
+    pub async fn sd_mmc_mci_install_mmc_async(&mut self) -> Result<(), MciError> {
+        self.sd_mmc_power_on_async().await?;
+        // CMD0 - Reset all cards to idle state.
+        self.mci
+            .send_command_async(SDMMC_MCI_CMD0_GO_IDLE_STATE.into(), 0)
+            .await?;
+        let mut timeout = self.sd_mmc_timeout(SD_MMC_INIT_TIMEOUT_US, SD_MMC_COMMAND_POLL_CYCLES);
+        while !self.mmc_cmd1_poll()? {
+            if timeout.expired(self.monotonic_clock.as_ref()) {
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
+            yield_now().await;
+        }
+        self.mmc_mci_install_powered_up()
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(feature = "sdio")]
+use crate::error::sdio_state_error;
+use crate::error::{card_status_error, CommandContext, CommandResponse, SdMmcCommandError};
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sd::card_status::CardStatusRegister;
+#[cfg(feature = "sdio")]
+use crate::sdio_state::SdioR5State;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Record a failed command in self.last_command_error
+    /// # Arguments
+    /// * `kind` What went wrong
+    /// * `command` Command that failed
+    pub fn sd_mmc_command_error(
+        &mut self,
+        kind: MciError,
+        command: CommandContext,
+    ) -> SdMmcCommandError {
+        self.last_command_error = Some(command);
+        SdMmcCommandError::new(kind, command)
+    }
+
+    /// Send a command with an R1 response and check the card status it answers
+    /// Card internal and address errors are given back as ImplError::Internal
+    /// # Arguments
+    /// * `cmd` Command
+    /// * `arg` Command argument
+    pub fn sd_mmc_command_r1(
+        &mut self,
+        cmd: u32,
+        arg: u32,
+    ) -> Result<CardStatusRegister, SdMmcCommandError> {
+        if let Err(err) = self.mci.send_command(cmd, arg) {
+            return Err(self.sd_mmc_command_error(err, CommandContext::new(cmd, arg, None)));
+        }
+        self.sd_mmc_check_r1(cmd, arg, MciError::Impl(ImplError::Internal))
+    }
+
+    /// Check the card status of the response to a command that was sent
+    /// # Arguments
+    /// * `cmd` Command that was sent
+    /// * `arg` Its argument
+    /// * `data_error` Error given back for address, ECC and card internal errors
+    pub(crate) fn sd_mmc_check_r1(
+        &mut self,
+        cmd: u32,
+        arg: u32,
+        data_error: MciError,
+    ) -> Result<CardStatusRegister, SdMmcCommandError> {
+        let status = match self.mci.get_response() {
+            Ok(val) => CardStatusRegister { val },
+            Err(err) => {
+                return Err(self.sd_mmc_command_error(err, CommandContext::new(cmd, arg, None)))
+            }
+        };
+        match card_status_error(&status, data_error) {
+            Some(kind) => {
+                let response = Some(CommandResponse::CardStatus(status));
+                Err(self.sd_mmc_command_error(kind, CommandContext::new(cmd, arg, response)))
+            }
+            None => Ok(status),
+        }
+    }
+
+    /// Check the flags of the R5 response to a CMD52 or CMD53 that was sent
+    /// Returns the data byte of the response
+    /// # Arguments
+    /// * `cmd` Command that was sent
+    /// * `arg` Its argument
+    /// * `data_error` Error given back for general errors
+    #[cfg(feature = "sdio")]
+    pub(crate) fn sdio_check_r5(
+        &mut self,
+        cmd: u32,
+        arg: u32,
+        data_error: MciError,
+    ) -> Result<u8, SdMmcCommandError> {
+        let response = match self.mci.get_response() {
+            Ok(val) => val,
+            Err(err) => {
+                return Err(self.sd_mmc_command_error(err, CommandContext::new(cmd, arg, None)))
+            }
+        };
+        let state = SdioR5State {
+            val: response as u16,
+        };
+        match sdio_state_error(&state, data_error) {
+            Some(kind) => {
+                let response = Some(CommandResponse::Sdio(state));
+                Err(self.sd_mmc_command_error(kind, CommandContext::new(cmd, arg, response)))
+            }
+            None => Ok(response as u8),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::card_state::CardState;
+use crate::command_arguments::mmc::BusWidth;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::timeout::{MonotonicClock, SD_MMC_DEBOUNCE_TIMEOUT_US};
+use embedded_error::mci::MciError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Change of the card in a slot
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CardEvent {
+    /// The card detect pin showed a card for the whole debounce time
+    /// Without a monotonic clock the debounce is an amount of polls, see MciCard::debounce_polls
+    Inserted,
+    /// The card detect pin shows no card anymore, after Inserted
+    Removed,
+    /// The card is installed after debouncing
+    Installed,
+    /// The install after debouncing failed, the card is Unusable
+    InstallFailed,
+    /// The write protect pin changed, whether the card is now write protected
+    WriteProtectChanged(bool),
+}
+
+/// Handler called on each change of the card in the slot
+pub type CardEventHandler<MCI, WP, DETECT, POWER, CLOCK> =
+    fn(&mut MciCard<MCI, WP, DETECT, POWER, CLOCK>, CardEvent);
+
+/// Install run once a newly inserted card is debounced, for example MciCard::sdio_install
+pub type CardInstall<MCI, WP, DETECT, POWER, CLOCK> =
+    fn(&mut MciCard<MCI, WP, DETECT, POWER, CLOCK>) -> Result<(), MciError>;
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Register the handler of the card events. None removes it
+    pub fn sd_mmc_register_card_event_handler(
+        &mut self,
+        handler: Option<CardEventHandler<MCI, WP, DETECT, POWER, CLOCK>>,
+    ) {
+        self.card_event_handler = handler;
+    }
+
+    /// Read the card detect and write protect pins and follow the insertions and removals
+    /// An inserted card goes to Debounce, a removed one to NoCard. A card that goes away while it
+    /// is debouncing is a bounce: the card goes back to NoCard without any event, and the debounce
+    /// starts over when the pin shows a card again. CardEvent::Inserted is sent once the debounce
+    /// time is over, see sd_mmc_debounce_elapsed
+    /// There is no bus traffic, so it can be called from the pin-change interrupt of the card
+    /// detect pin
+    pub fn sd_mmc_card_detect_changed(&mut self) -> Result<(), MciError> {
+        if !self.card_detected()? {
+            if self.state == CardState::Debounce {
+                self.state = CardState::NoCard;
+                self.debounce_start_us = None;
+                self.debounce_polls_counted = 0;
+            } else {
+                self.sd_mmc_set_no_card();
+            }
+            return Ok(());
+        }
+
+        if self.state == CardState::NoCard {
+            self.state = CardState::Debounce;
+            self.debounce_start_us = self.monotonic_clock.as_ref().map(|clock| clock.now_us());
+            self.debounce_polls_counted = 0;
+        }
+        if self.state == CardState::Debounce {
+            // The write protect pin is followed once the card is in
+            return Ok(());
+        }
+
+        let write_protected = self.write_protected()?;
+        if let Some(last) = self.last_write_protected {
+            if last != write_protected {
+                self.sd_mmc_card_event(CardEvent::WriteProtectChanged(write_protected));
+            }
+        }
+        self.last_write_protected = Some(write_protected);
+        Ok(())
+    }
+
+    /// Follow the card detect pin, then power the card on and install it with self.card_install
+    /// once it is debounced
+    /// Without self.card_install the card is left in Init for the application to install
+    /// To be called periodically, for example from the main loop or a timer
+    pub fn sd_mmc_poll_card_detect(&mut self) -> Result<(), MciError> {
+        self.sd_mmc_card_detect_changed()?;
+        if self.state != CardState::Debounce || !self.sd_mmc_debounce_elapsed() {
+            return Ok(());
+        }
+        if let Some(install) = self.card_install {
+            match self.sd_mmc_power_on().and_then(|_| install(self)) {
+                Ok(()) => {
+                    self.state = CardState::Ready;
+                    self.sd_mmc_card_event(CardEvent::Installed);
+                }
+                Err(_) => {
+                    self.state = CardState::Unusable;
+                    self.sd_mmc_card_event(CardEvent::InstallFailed);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move a debouncing card to Init once the debounce time is over, and send
+    /// CardEvent::Inserted
+    /// The card detect pin must have been followed with sd_mmc_card_detect_changed meanwhile, so
+    /// a bounce restarted the debounce
+    /// Without a monotonic clock each call is a poll, the debounce is over after
+    /// self.debounce_polls of them
+    /// Returns false while the card is still debouncing
+    pub(crate) fn sd_mmc_debounce_elapsed(&mut self) -> bool {
+        match self.monotonic_clock.as_ref() {
+            Some(clock) => {
+                let now = clock.now_us();
+                let start = *self.debounce_start_us.get_or_insert(now);
+                if now.wrapping_sub(start) < SD_MMC_DEBOUNCE_TIMEOUT_US {
+                    return false;
+                }
+            }
+            None => {
+                self.debounce_polls_counted = self.debounce_polls_counted.saturating_add(1);
+                if self.debounce_polls_counted < self.debounce_polls {
+                    return false;
+                }
+            }
+        }
+        self.debounce_start_us = None;
+        self.debounce_polls_counted = 0;
+        self.state = CardState::Init;
+        // Set 1-bit bus width and low clock for initialization
+        self.clock = 400_000;
+        self.bus_width = BusWidth::_1BIT;
+        self.high_speed = false;
+        self.sd_mmc_card_event(CardEvent::Inserted);
+        true
+    }
+
+    pub(crate) fn sd_mmc_card_event(&mut self, event: CardEvent) {
+        if let Some(handler) = self.card_event_handler {
+            handler(self, event);
+        }
+    }
+}
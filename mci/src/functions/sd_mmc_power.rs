@@ -0,0 +1,106 @@
+use crate::command_arguments::mmc::BusWidth;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Switch the card supply with the power pin, without waiting for it to ramp
+    /// Without a power pin the card is always powered, powering it off is an InvalidConfiguration
+    /// The ramp is waited on self.monotonic_clock: without it the pin is not touched and the error
+    /// is InvalidConfiguration, unless the ramp time is 0
+    /// A failed pin write is an Internal error
+    /// # Arguments
+    /// * `on` Whether the card is powered
+    pub fn sd_mmc_set_power(&mut self, on: bool) -> Result<(), MciError> {
+        let ramp_us = if on {
+            self.power_ramp_up_us
+        } else {
+            self.power_ramp_down_us
+        };
+        if self.power.is_some() && ramp_us != 0 && self.monotonic_clock.is_none() {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        let high = on == self.power_high_activated;
+        match self.power.as_mut() {
+            Some(pin) if high => pin.set_high(),
+            Some(pin) => pin.set_low(),
+            None if on => return Ok(()),
+            None => return Err(MciError::Impl(ImplError::InvalidConfiguration)),
+        }
+        .map_err(|_| MciError::Impl(ImplError::Internal))
+    }
+
+    /// Power on sequence: the supply is switched on and ramps up, then the card gets the 74 clocks
+    /// it needs before the first command, at 400KHz on a 1-bit bus
+    /// The installs start with it, so a card that was powered off is powered on again
+    pub fn sd_mmc_power_on(&mut self) -> Result<(), MciError> {
+        self.sd_mmc_set_power(true)?;
+        if self.power.is_some() {
+            self.sd_mmc_power_delay(self.power_ramp_up_us)?;
+        }
+        self.sd_mmc_power_on_clocks()
+    }
+
+    /// The 74 clocks of the power on sequence, once the supply is up
+    pub(crate) fn sd_mmc_power_on_clocks(&mut self) -> Result<(), MciError> {
+        // Set 1-bit bus width and low clock for initialization
+        self.clock = 400_000;
+        self.bus_width = BusWidth::_1BIT;
+        self.high_speed = false;
+        self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        self.mci.send_clock()
+    }
+
+    /// Power off the card and wait for the supply to drop
+    /// An eMMC is to be notified first, see mmc_power_off_notification
+    pub fn sd_mmc_power_off(&mut self) -> Result<(), MciError> {
+        // The host stops driving the card before its supply goes away
+        let _ = self.sd_mmc_deselect_this_device();
+        self.sd_mmc_set_power(false)?;
+        self.sd_mmc_power_delay(self.power_ramp_down_us)
+    }
+
+    /// Whether the card can be powered off and on again: it has a power pin, and the ramps can be
+    /// waited on self.monotonic_clock or take no time
+    pub fn sd_mmc_can_power_cycle(&self) -> bool {
+        self.power.is_some()
+            && (self.monotonic_clock.is_some()
+                || (self.power_ramp_up_us == 0 && self.power_ramp_down_us == 0))
+    }
+
+    /// Power the card off and on again, to reset a card that no command brings back
+    /// The card is to be installed afterwards
+    /// A card that cannot be power cycled (sd_mmc_can_power_cycle) is left powered, and the error
+    /// is InvalidConfiguration
+    pub fn sd_mmc_power_cycle(&mut self) -> Result<(), MciError> {
+        if !self.sd_mmc_can_power_cycle() {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        self.sd_mmc_power_off()?;
+        self.sd_mmc_power_on()
+    }
+
+    /// Wait on self.monotonic_clock, there is no other time source to wait on
+    fn sd_mmc_power_delay(&self, duration_us: u32) -> Result<(), MciError> {
+        if duration_us == 0 {
+            return Ok(());
+        }
+        let clock = self
+            .monotonic_clock
+            .as_ref()
+            .ok_or(MciError::Impl(ImplError::InvalidConfiguration))?;
+        let start = clock.now_us();
+        while clock.now_us().wrapping_sub(start) < duration_us {}
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use crate::card_state::CardState;
+use crate::commands::SDMMC_CMD12_STOP_TRANSMISSION;
+use crate::error::SdMmcCommandError;
+use crate::functions::sdmmc::sd_mmc_blocks_amount;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::recovery::{CommandAttempts, RecoveryAction};
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Run a command, again on CRC and timeout errors, up to the command attempts of
+    /// self.recovery_policy
+    /// # Arguments
+    /// * `command` Sends the command
+    pub fn sd_mmc_with_command_retry<T, F>(&mut self, mut command: F) -> Result<T, MciError>
+    where
+        F: FnMut(&mut Self) -> Result<T, MciError>,
+    {
+        let mut attempts = CommandAttempts::new(&self.recovery_policy);
+        loop {
+            match command(self) {
+                Err(err) if attempts.retry(&err) => {}
+                result => return result,
+            }
+        }
+    }
+
+    /// CMD12: Stop the ongoing multi block transfer, retried on errors
+    pub fn sd_mmc_stop_transmission(&mut self) -> Result<(), MciError> {
+        self.sd_mmc_with_command_retry(|card| {
+            card.mci.adtc_stop(SDMMC_CMD12_STOP_TRANSMISSION.into(), 0)
+        })
+    }
+
+    /// Lower the bus clock: high speed goes back to default speed, default speed is halved down to
+    /// the minimum clock of self.recovery_policy
+    /// Returns false when the clock is already at the minimum
+    pub fn sd_mmc_step_clock_down(&mut self) -> Result<bool, MciError> {
+        if self.high_speed {
+            self.high_speed = false;
+            self.clock /= 2;
+        } else if self.clock / 2 >= self.recovery_policy.min_clock {
+            self.clock /= 2;
+        } else {
+            return Ok(false);
+        }
+        self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        Ok(true)
+    }
+
+    /// Whether sd_mmc_reinstall has a way to install the card again
+    /// A card that was never installed has none
+    pub fn sd_mmc_can_reinstall(&self) -> bool {
+        #[cfg(feature = "mmc")]
+        {
+            if self.card_type.mmc() {
+                return true;
+            }
+        }
+        #[cfg(feature = "sdio")]
+        {
+            if self.card_type.sdio() {
+                return true;
+            }
+        }
+        self.card_type.sd()
+    }
+
+    /// Install the card again, the same way it was installed
+    /// A card with a power pin is powered off first, the install powers it on again
+    /// A card without a way to install it again (sd_mmc_can_reinstall) is left as it is, and the
+    /// error is InvalidConfiguration
+    pub fn sd_mmc_reinstall(&mut self) -> Result<(), MciError> {
+        if !self.sd_mmc_can_reinstall() {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        if self.power.is_some() {
+            self.sd_mmc_power_off()?;
+        }
+        // The IO of an SDIO or combo card is installed again with its memory
+        #[cfg(feature = "sdio")]
+        {
+            if self.card_type.sdio() {
+                return self.sdio_recover(None);
+            }
+        }
+        self.state = CardState::Init;
+        let result = self.sd_mmc_reinstall_memory();
+        self.state = if result.is_ok() {
+            CardState::Ready
+        } else {
+            CardState::Unusable
+        };
+        result
+    }
+
+    fn sd_mmc_reinstall_memory(&mut self) -> Result<(), MciError> {
+        #[cfg(feature = "mmc")]
+        {
+            if self.card_type.mmc() {
+                self.card_type.set_unknown();
+                return self.sd_mmc_mci_install_mmc();
+            }
+        }
+        self.sd_mmc_mci_install_sd()
+    }
+
+    /// Bring the card back to transfer state after a failed transfer, then act on the decision of
+    /// self.recovery_policy
+    /// Returns Ok when the transfer is to be tried again, else the error to give back
+    /// A Reinitialize decision on a card that cannot be installed again is a GiveUp
+    /// # Arguments
+    /// * `error` Error of the failed transfer
+    pub fn sd_mmc_recover_transfer(&mut self, error: MciError) -> Result<(), MciError> {
+        self.recovery_state.failed(&error);
+        let action = match self.recovery_policy.decide(&self.recovery_state, &error) {
+            RecoveryAction::Reinitialize if !self.sd_mmc_can_reinstall() => RecoveryAction::GiveUp,
+            action => action,
+        };
+        if action == RecoveryAction::Fail {
+            return Err(error);
+        }
+
+        // The card may still be sending or receiving data, or programming
+        let _ = self.mci.reset_data_line();
+        let _ = self.sd_mmc_stop_transmission();
+        let _ = self.sd_mmc_cmd13_get_status_and_wait_for_ready_for_data_flag();
+
+        match action {
+            RecoveryAction::Retry => Ok(()),
+            RecoveryAction::StepClockDown => {
+                self.recovery_state.consecutive_crc_errors = 0;
+                self.sd_mmc_step_clock_down()?;
+                Ok(())
+            }
+            RecoveryAction::Reinitialize => {
+                let reinits = self.recovery_state.reinits + 1;
+                self.recovery_state.succeeded();
+                self.recovery_state.reinits = reinits;
+                self.sd_mmc_reinstall()
+            }
+            RecoveryAction::Fail => Err(error),
+            RecoveryAction::GiveUp => {
+                self.state = CardState::Unusable;
+                Err(error)
+            }
+        }
+    }
+
+    /// Read blocks, recovering from failed transfers as self.recovery_policy decides
+    /// A removed card gives back CardRemoved
+    /// # Arguments
+    /// * `start` First block
+    /// * `destination` Buffer to fill, a multiple of the block size
+    pub fn sd_mmc_read_blocks_with_recovery(
+        &mut self,
+        start: u32,
+        destination: &mut [u8],
+    ) -> Result<(), SdMmcCommandError> {
+        let blocks_amount = sd_mmc_blocks_amount(destination.len())?;
+        loop {
+            match self.sd_mmc_read_blocks_once(start, destination, blocks_amount) {
+                Ok(()) => {
+                    self.recovery_state.succeeded();
+                    return Ok(());
+                }
+                Err(err) => {
+                    // The blocks of a failed command are not counted as transferred
+                    let err = self.sd_mmc_transfer_error(err, 0);
+                    if err.removed.is_some() {
+                        return Err(err);
+                    }
+                    self.sd_mmc_recover_transfer(err.kind)?
+                }
+            }
+        }
+    }
+
+    /// Write blocks, recovering from failed transfers as self.recovery_policy decides
+    /// A removed card gives back CardRemoved
+    /// # Arguments
+    /// * `start` First block
+    /// * `data` Data to write, a multiple of the block size
+    pub fn sd_mmc_write_blocks_with_recovery(
+        &mut self,
+        start: u32,
+        data: &[u8],
+    ) -> Result<(), SdMmcCommandError> {
+        let blocks_amount = sd_mmc_blocks_amount(data.len())?;
+        loop {
+            match self.sd_mmc_write_blocks_once(start, data, blocks_amount) {
+                Ok(()) => {
+                    self.recovery_state.succeeded();
+                    return Ok(());
+                }
+                Err(err) => {
+                    // The blocks of a failed command are not counted as transferred
+                    let err = self.sd_mmc_transfer_error(err, 0);
+                    if err.removed.is_some() {
+                        return Err(err);
+                    }
+                    self.sd_mmc_recover_transfer(err.kind)?
+                }
+            }
+        }
+    }
+
+    /// The errors of the data phase are kept, so CRC errors can be told apart
+    fn sd_mmc_read_blocks_once(
+        &mut self,
+        start: u32,
+        destination: &mut [u8],
+        blocks_amount: u16,
+    ) -> Result<(), MciError> {
+        let mut transaction = self.sd_mmc_init_read_blocks(start, blocks_amount)?;
+        self.mci.read_blocks(destination, blocks_amount)?;
+        transaction.remaining = 0;
+        self.sd_mmc_wait_end_of_read_blocks(false, &mut transaction)
+    }
+
+    fn sd_mmc_write_blocks_once(
+        &mut self,
+        start: u32,
+        data: &[u8],
+        blocks_amount: u16,
+    ) -> Result<(), MciError> {
+        let mut transaction = self.sd_mmc_init_write_blocks(start, blocks_amount)?;
+        self.mci.write_blocks(data, blocks_amount)?;
+        transaction.remaining = 0;
+        self.sd_mmc_wait_end_of_write_blocks(false, &mut transaction)
+    }
+}
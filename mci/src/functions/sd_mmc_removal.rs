@@ -0,0 +1,99 @@
+use crate::card_state::CardState;
+use crate::error::{CardRemoved, SdMmcCommandError};
+use crate::functions::sd_mmc_hot_plug::CardEvent;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::{CommandOrDataError, MciError};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// How a card removed during an operation is noticed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CardRemovalDetection {
+    /// Removals are not looked for, the errors of the operation are given back as they are
+    Disabled,
+    /// The card detect pin shows no card, for slots with a real card detect pin
+    DetectPin,
+    /// The card does not answer a command, for slots without a card detect pin
+    CommandTimeout,
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Check the card is still there during a long operation
+    /// A removed card is aborted with sd_mmc_abort_removed and the error is NoCard
+    /// Only the card detect pin is checked, with CardRemovalDetection::DetectPin. Nothing is checked
+    /// otherwise, see sd_mmc_check_removal
+    pub fn sd_mmc_check_card_present(&mut self) -> Result<(), MciError> {
+        if self.removal_detection == CardRemovalDetection::DetectPin && !self.card_detected()? {
+            self.sd_mmc_abort_removed();
+            return Err(MciError::NoCard);
+        }
+        Ok(())
+    }
+
+    /// Tell whether an operation failed because the card was removed
+    /// A removed card is aborted with sd_mmc_abort_removed and NoCard is given back, otherwise the
+    /// error itself
+    /// # Arguments
+    /// * `error` Error of the operation
+    pub fn sd_mmc_check_removal(&mut self, error: MciError) -> MciError {
+        let removed = match self.removal_detection {
+            CardRemovalDetection::Disabled => false,
+            CardRemovalDetection::DetectPin => {
+                matches!(error, MciError::NoCard) || matches!(self.card_detected(), Ok(false))
+            }
+            CardRemovalDetection::CommandTimeout => matches!(
+                error,
+                MciError::NoCard | MciError::CommandError(CommandOrDataError::Timeout)
+            ),
+        };
+        if removed {
+            self.sd_mmc_abort_removed();
+            MciError::NoCard
+        } else {
+            error
+        }
+    }
+
+    /// Error of a failed transfer, CardRemoved when the card was removed during it
+    /// # Arguments
+    /// * `error` Error of the transfer
+    /// * `blocks_completed` Blocks transferred before the failure
+    pub fn sd_mmc_transfer_error(
+        &mut self,
+        error: MciError,
+        blocks_completed: u32,
+    ) -> SdMmcCommandError {
+        match self.sd_mmc_check_removal(error) {
+            MciError::NoCard if self.state == CardState::NoCard => {
+                CardRemoved { blocks_completed }.into()
+            }
+            error => error.into(),
+        }
+    }
+
+    /// Abort everything on a removed card: the host is reset and the card is NoCard
+    pub fn sd_mmc_abort_removed(&mut self) {
+        // The host may be left in the middle of a command or a data transfer
+        let _ = self.mci.reset_host();
+        self.sd_mmc_set_no_card();
+    }
+
+    /// Forget the removed card, CardEvent::Removed is sent once
+    pub(crate) fn sd_mmc_set_no_card(&mut self) {
+        if self.state != CardState::NoCard {
+            self.state = CardState::NoCard;
+            self.debounce_start_us = None;
+            self.debounce_polls_counted = 0;
+            self.last_write_protected = None;
+            self.sd_mmc_card_event(CardEvent::Removed);
+        }
+    }
+}
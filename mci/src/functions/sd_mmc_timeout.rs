@@ -0,0 +1,88 @@
+use crate::mci::Mci;
+use crate::mci_card::{MciCard, SD_TRANS_MULTIPLIERS};
+use crate::timeout::{
+    MonotonicClock, Timeout, SDXC_WRITE_TIMEOUT_US, SD_READ_TIMEOUT_US, SD_WRITE_TIMEOUT_US,
+};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Bus clock cycles of a command and its short response, (6+6)*8
+pub const SD_MMC_COMMAND_POLL_CYCLES: u32 = 96;
+/// Bus clock cycles of a CMD55 and ACMD41 with their responses, (6+6+6+6)*8
+pub const SD_ACMD41_POLL_CYCLES: u32 = 192;
+/// Bus clock cycles of a CMD5 and its response, (6+4)*8
+pub const SDIO_CMD5_POLL_CYCLES: u32 = 80;
+
+/// TAAC time units in ns
+const SD_MMC_TAAC_UNITS_NS: [u64; 8] = [1, 10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+/// SDXC cards are bigger than 32GB, in KBytes
+const SDXC_MIN_CAPACITY: u32 = 32 * 1024 * 1024;
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Deadline of a wait on the card, from now
+    /// # Arguments
+    /// * `duration_us` Time to wait
+    /// * `cycles_per_poll` Bus clock cycles of a poll, for when there is no monotonic clock
+    pub fn sd_mmc_timeout(&self, duration_us: u32, cycles_per_poll: u32) -> Timeout {
+        Timeout::new(
+            self.monotonic_clock.as_ref(),
+            duration_us,
+            self.clock,
+            cycles_per_poll,
+        )
+    }
+
+    /// Typical read access time from the CSD, TAAC + NSAC at the current clock, in us
+    pub fn sd_mmc_read_access_time_us(&self) -> u32 {
+        let taac = self.csd.read_access_time();
+        let taac_ns = SD_MMC_TAAC_UNITS_NS[(taac & 0x7) as usize]
+            * SD_TRANS_MULTIPLIERS[((taac >> 3) & 0xF) as usize] as u64
+            / 10;
+        let nsac_us =
+            self.csd.read_access_clock_cycles() as u64 * 100 * 1_000_000 / self.clock.max(1) as u64;
+        (taac_ns / 1000 + nsac_us).min(u32::MAX as u64) as u32
+    }
+
+    /// Read timeout in us
+    /// SD cards use 100ms. MMC cards use 10 times the read access time, never less than the SD
+    /// timeout
+    pub fn sd_mmc_read_timeout_us(&self) -> u32 {
+        if self.card_type.mmc() {
+            self.sd_mmc_read_access_time_us()
+                .saturating_mul(10)
+                .max(SD_READ_TIMEOUT_US)
+        } else {
+            SD_READ_TIMEOUT_US
+        }
+    }
+
+    /// Write timeout in us, also the time the card may stay busy programming
+    /// SDSC and SDHC cards use 250ms, SDXC cards 500ms. MMC cards use the read timeout times
+    /// 2^R2W_FACTOR, never less than the SD timeout
+    pub fn sd_mmc_write_timeout_us(&self) -> u32 {
+        if self.card_type.mmc() {
+            let factor = self.csd.write_speed_factor() as u32;
+            self.sd_mmc_read_timeout_us()
+                .saturating_mul(1 << factor)
+                .max(SD_WRITE_TIMEOUT_US)
+        } else if self.capacity > SDXC_MIN_CAPACITY {
+            SDXC_WRITE_TIMEOUT_US
+        } else {
+            SD_WRITE_TIMEOUT_US
+        }
+    }
+
+    /// Erase timeout in us, the write timeout for each erased block
+    /// # Arguments
+    /// * `blocks_amount` Amount of erased blocks
+    pub fn sd_mmc_erase_timeout_us(&self, blocks_amount: u32) -> u32 {
+        self.sd_mmc_write_timeout_us()
+            .saturating_mul(blocks_amount.max(1))
+    }
+}
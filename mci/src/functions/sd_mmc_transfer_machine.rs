@@ -0,0 +1,344 @@
+use crate::card_state::CardState;
+use crate::commands::SDMMC_MCI_CMD13_SEND_STATUS;
+use crate::error::{CardRemoved, SdMmcCommandError};
+use crate::functions::sd_mmc_timeout::SD_MMC_COMMAND_POLL_CYCLES;
+use crate::functions::sdmmc::{sd_mmc_blocks_amount, SD_MMC_BLOCK_SIZE};
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sd::card_status::CardStatusRegister;
+use crate::timeout::{MonotonicClock, Timeout};
+use crate::transfer::{TransferPhase, TransferTransaction};
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Buffer of a TransferMachine, which also tells the direction of the transfer
+pub enum TransferBuffer<'a> {
+    Read(&'a mut [u8]),
+    Write(&'a [u8]),
+}
+
+/// Outcome of a TransferMachine poll
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TransferStatus {
+    /// The transfer needs more polls
+    Pending,
+    /// All the blocks are transferred
+    Done,
+}
+
+/// Block read or write that advances one step per poll, and never waits on the card
+/// A step is a CMD13 status poll, the read or write command, one block of data, a check for the
+/// end of the data transfer or the CMD12 stop.
+/// It is meant to be polled from an interrupt handler or a super loop, the card is only borrowed
+/// for the duration of a poll.
+pub struct TransferMachine<'a> {
+    transaction: TransferTransaction,
+    buffer: TransferBuffer<'a>,
+    /// Deadline of the ongoing CMD13 wait
+    timeout: Option<Timeout>,
+}
+
+impl<'a> TransferMachine<'a> {
+    /// Read blocks into a buffer
+    /// # Arguments
+    /// * `start` First block
+    /// * `destination` Buffer to fill, a multiple of the block size
+    pub fn read(start: u32, destination: &'a mut [u8]) -> Result<TransferMachine<'a>, MciError> {
+        let amount = sd_mmc_blocks_amount(destination.len())?;
+        Ok(Self::new(start, amount, TransferBuffer::Read(destination)))
+    }
+
+    /// Write blocks from a buffer
+    /// # Arguments
+    /// * `start` First block
+    /// * `data` Data to write, a multiple of the block size
+    pub fn write(start: u32, data: &'a [u8]) -> Result<TransferMachine<'a>, MciError> {
+        let amount = sd_mmc_blocks_amount(data.len())?;
+        Ok(Self::new(start, amount, TransferBuffer::Write(data)))
+    }
+
+    fn new(start: u32, amount: u16, buffer: TransferBuffer<'a>) -> TransferMachine<'a> {
+        TransferMachine {
+            transaction: TransferTransaction::new(start, amount),
+            buffer,
+            timeout: None,
+        }
+    }
+
+    /// Progress of the transfer
+    pub fn transaction(&self) -> &TransferTransaction {
+        &self.transaction
+    }
+
+    /// Step the transfer is at
+    pub fn phase(&self) -> TransferPhase {
+        self.transaction.phase
+    }
+
+    /// Take back the buffer, after the transfer is done or failed
+    pub fn release(self) -> TransferBuffer<'a> {
+        self.buffer
+    }
+
+    /// Run the next step of the transfer
+    /// After an error the transfer is Failed, and polling it again is an InvalidConfiguration
+    /// A removed card is aborted and the error is NoCard
+    /// # Arguments
+    /// * `card` Card the transfer started on
+    pub fn poll<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<TransferStatus, MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        let result = match self.transaction.phase {
+            TransferPhase::Done | TransferPhase::Failed => self.step(card),
+            _ => card
+                .sd_mmc_check_card_present()
+                .and_then(|_| self.step(card))
+                .map_err(|err| card.sd_mmc_check_removal(err)),
+        };
+        match result {
+            Ok(()) if self.transaction.phase == TransferPhase::Done => Ok(TransferStatus::Done),
+            Ok(()) => Ok(TransferStatus::Pending),
+            Err(err) => {
+                if matches!(
+                    self.transaction.phase,
+                    TransferPhase::Data | TransferPhase::DataEnd
+                ) {
+                    self.abort_data(card);
+                }
+                self.transaction.phase = TransferPhase::Failed;
+                Err(err)
+            }
+        }
+    }
+
+    /// Poll the transfer until it is done
+    /// A removed card gives back CardRemoved with the blocks transferred before the removal
+    /// # Arguments
+    /// * `card` Card the transfer started on
+    pub fn run<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), SdMmcCommandError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        loop {
+            match self.poll(card) {
+                Ok(TransferStatus::Done) => return Ok(()),
+                Ok(TransferStatus::Pending) => {}
+                Err(MciError::NoCard) if card.state == CardState::NoCard => {
+                    return Err(CardRemoved {
+                        blocks_completed: self.transaction.transferred() as u32,
+                    }
+                    .into());
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    fn step<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        match self.transaction.phase {
+            TransferPhase::WaitReady => {
+                if self.timeout.is_none() {
+                    card.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+                }
+                if self.poll_ready_for_data(card)? {
+                    self.transaction.phase = TransferPhase::Command;
+                }
+            }
+            TransferPhase::Command => self.start_command(card)?,
+            TransferPhase::Data => self.transfer_block(card)?,
+            TransferPhase::DataEnd => {
+                if card.mci.is_transfer_complete()? {
+                    match self.buffer {
+                        TransferBuffer::Read(_) => card.mci.wait_until_read_finished()?,
+                        TransferBuffer::Write(_) => card.mci.wait_until_write_finished()?,
+                    }
+                    self.transaction.phase = if self.transaction.needs_stop(false) {
+                        TransferPhase::Stop
+                    } else {
+                        self.after_data_phase()
+                    };
+                }
+            }
+            TransferPhase::Stop => {
+                card.sd_mmc_stop_transmission()?;
+                self.transaction.phase = self.after_data_phase();
+            }
+            TransferPhase::Busy => {
+                if self.poll_ready_for_data(card)? {
+                    self.transaction.phase = TransferPhase::Done;
+                }
+            }
+            TransferPhase::Done => {}
+            TransferPhase::Failed => return Err(MciError::Impl(ImplError::InvalidConfiguration)),
+        }
+        Ok(())
+    }
+
+    /// CMD13 once, true when the card is ready for data
+    /// The card may still be programming a previous write, so the write timeout applies
+    fn poll_ready_for_data<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<bool, MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        let timeout = self.timeout.get_or_insert_with(|| {
+            card.sd_mmc_timeout(card.sd_mmc_write_timeout_us(), SD_MMC_COMMAND_POLL_CYCLES)
+        });
+        card.mci
+            .send_command(SDMMC_MCI_CMD13_SEND_STATUS.into(), (card.rca as u32) << 16)?;
+        let status = CardStatusRegister {
+            val: card.mci.get_response()?,
+        };
+        if status.ready_for_data() {
+            self.timeout = None;
+            return Ok(true);
+        }
+        if timeout.expired(card.monotonic_clock.as_ref()) {
+            return Err(MciError::Impl(ImplError::TimedOut));
+        }
+        Ok(false)
+    }
+
+    /// Send the read or write command, with CMD23 when the card supports it
+    /// The data phase starts as soon as the host started the command
+    fn start_command<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        let write = match self.buffer {
+            TransferBuffer::Read(_) => false,
+            TransferBuffer::Write(_) => true,
+        };
+        if write && card.write_protected()? {
+            return Err(MciError::WriteProtected);
+        }
+        let command =
+            card.sd_mmc_block_command(write, self.transaction.lba, self.transaction.amount, false);
+        self.transaction.block_count_set = command.block_count_set;
+        let started = card.sd_mmc_adtc_start_block_command(&command);
+        if started.is_ok() {
+            self.transaction.phase = TransferPhase::Data;
+        }
+        card.sd_mmc_check_block_command(&command, started)?;
+        Ok(())
+    }
+
+    /// Move the next block over the data line
+    /// Nothing is done until the host buffer is ready, the next poll checks again
+    fn transfer_block<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        let block_size = SD_MMC_BLOCK_SIZE as usize;
+        let offset = self.transaction.transferred() as usize * block_size;
+        let ready = match self.buffer {
+            TransferBuffer::Read(_) => card.mci.is_read_buffer_ready()?,
+            TransferBuffer::Write(_) => card.mci.is_write_buffer_ready()?,
+        };
+        if !ready {
+            return Ok(());
+        }
+        match &mut self.buffer {
+            TransferBuffer::Read(destination) => {
+                if card
+                    .mci
+                    .read_blocks(&mut destination[offset..offset + block_size], 1)
+                    .is_err()
+                {
+                    return Err(MciError::ReadError);
+                }
+            }
+            TransferBuffer::Write(data) => {
+                if card
+                    .mci
+                    .write_blocks(&data[offset..offset + block_size], 1)
+                    .is_err()
+                {
+                    return Err(MciError::WriteError);
+                }
+            }
+        }
+        self.transaction.remaining -= 1;
+        if self.transaction.remaining == 0 {
+            self.transaction.phase = TransferPhase::DataEnd;
+        }
+        Ok(())
+    }
+
+    /// End a data phase that failed, so neither the host nor the card is left in the transfer
+    /// The data line is reset, and a multi block transfer the card does not end by itself is
+    /// stopped with CMD12. A removed card was already aborted
+    fn abort_data<MCI, WP, DETECT, POWER, CLOCK>(
+        &self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        if card.state == CardState::NoCard {
+            return;
+        }
+        // Best effort, the error of the failed step is the one reported
+        let _ = card.mci.reset_data_line();
+        if self.transaction.needs_stop(true) {
+            let _ = card.sd_mmc_stop_transmission();
+        }
+    }
+
+    /// A write waits for the card to be done programming, a read is done
+    fn after_data_phase(&self) -> TransferPhase {
+        match self.buffer {
+            TransferBuffer::Read(_) => TransferPhase::Done,
+            TransferBuffer::Write(_) => TransferPhase::Busy,
+        }
+    }
+}
@@ -1,82 +1,114 @@
+use crate::card_state::CardState;
 use crate::command_arguments::mmc::BusWidth;
 use crate::command_arguments::sdio::cmd52::{Cmd52, Direction};
-use crate::command_arguments::sdio::cmd53::Cmd53;
+use crate::command_arguments::sdio::cmd53::{Cmd53, TransferCount};
 use crate::commands::{
-    SDIO_CMD52_IO_RW_DIRECT, SDIO_CMD53_IO_R_BLOCK_EXTENDED, SDIO_CMD53_IO_W_BLOCK_EXTENDED,
-    SDIO_CMD5_SEND_OP_COND,
+    SDIO_CMD52_IO_RW_DIRECT, SDIO_CMD53_IO_R_BLOCK_EXTENDED, SDIO_CMD53_IO_R_BYTE_EXTENDED,
+    SDIO_CMD53_IO_W_BLOCK_EXTENDED, SDIO_CMD53_IO_W_BYTE_EXTENDED, SDIO_CMD5_SEND_OP_COND,
+    SDMMC_CMD7_SELECT_CARD_CMD, SDMMC_MCI_CMD0_GO_IDLE_STATE, SD_CMD3_SEND_RELATIVE_ADDR,
 };
+use crate::error::{CommandContext, SdMmcCommandError};
+use crate::functions::sd_mmc_timeout::{SDIO_CMD5_POLL_CYCLES, SD_MMC_COMMAND_POLL_CYCLES};
+use crate::functions::sdmmc::SD_MMC_BLOCK_SIZE;
 use crate::mci::Mci;
 use crate::mci_card::{ocr_voltage_support, MciCard, SD_MMC_TRANS_UNITS, SD_TRANS_MULTIPLIERS};
 use crate::registers::ocr::OcrRegister;
-use crate::registers::register_address::RegisterAddress;
 use crate::registers::sdio::cccr::bus_interface::BusInterfaceControlRegister;
 use crate::registers::sdio::cccr::card_capability::CardCapabilityRegister;
+use crate::registers::sdio::cccr::cccr_sdio_revision::CccrSdioRevisionRegister;
 use crate::registers::sdio::cccr::function_select::FunctionSelection;
 use crate::registers::sdio::cccr::high_speed::HighSpeedRegister;
+use crate::registers::sdio::cccr::interrupt_enable::InterruptEnableRegister;
+use crate::registers::sdio::cccr::io_enable::IoEnableRegister;
+use crate::registers::sdio::fbr::block_size::BlockSize;
+use crate::registers::sdio::fbr::cis_pointer::CisPointerRegister;
+use crate::registers::sdio::fbr::csa_and_interface::CsaAndInterfaceCodeRegister;
+use crate::registers::sdio::fbr::extended_interface_code::ExtendedInterfaceCodeRegister;
+use crate::registers::sdio::sdio_register::SdioRegisterSpace;
+use crate::timeout::{MonotonicClock, Timeout, SD_MMC_INIT_TIMEOUT_US};
 use embedded_error::mci::MciError;
 use embedded_error::ImplError;
-use embedded_hal::digital::v2::InputPin;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
 
 pub const SDIO_CCCR_CIS_PTR: u32 = 0x09;
-pub const SDIO_CISTPL_END: u8 = 0xFF;
-pub const SDIO_CISTPL_FUNCE: u8 = 0x22;
+/// Size of each function's FBR area. FBR n starts at n * SDIO_FBR_SIZE
+pub const SDIO_FBR_SIZE: u32 = 0x100;
+/// Largest byte count of a CMD53 in byte mode
+pub const SDIO_CMD53_MAX_BYTES: u16 = 512;
+/// Largest block count of a CMD53 in block mode, 0 being an infinite transfer
+pub const SDIO_CMD53_MAX_BLOCKS: u16 = 511;
+/// Last address of a function, CMD52 and CMD53 addresses are 17 bits
+pub const SDIO_MAX_ADDRESS: u32 = 0x1_FFFF;
 
-impl<MCI, WP, DETECT> MciCard<MCI, WP, DETECT>
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
 where
     MCI: Mci,
     WP: InputPin,
     DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
 {
     /// Try to get the SDIO card's operating condition
     pub fn sdio_send_operation_condition_command(&mut self) -> Result<(), MciError> {
+        let arg = match self.sdio_cmd5_probe()? {
+            Some(arg) => arg,
+            // No error but card type not updated
+            None => return Ok(()),
+        };
+
+        // Wait until card is ready
+        let mut timeout = self.sd_mmc_timeout(SD_MMC_INIT_TIMEOUT_US, SDIO_CMD5_POLL_CYCLES);
+        while !self.sdio_cmd5_poll(arg)? {
+            if timeout.expired(self.monotonic_clock.as_ref()) {
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// CMD5 without voltage window, to find out if there is an IO card
+    /// self.sdio_functions is updated
+    /// Returns the voltage window to negotiate, None if there is no IO
+    pub(crate) fn sdio_cmd5_probe(&mut self) -> Result<Option<u32>, MciError> {
         if self
             .mci
             .send_command(SDIO_CMD5_SEND_OP_COND.into(), 0)
             .is_err()
         {
-            // No error but card type not updated
-            return Ok(());
+            return Ok(None);
         }
         let resp = self.mci.get_response()?;
         let resp = OcrRegister { val: resp };
-        if !resp.number_of_io_functions() {
-            // No error but card type not updated
-            return Ok(());
+        if resp.io_functions_amount() == 0 {
+            return Ok(None);
         }
+        self.sdio_functions = resp.io_functions_amount();
+        Ok(Some(resp.val & ocr_voltage_support().val))
+    }
 
-        let arg = OcrRegister {
-            val: resp.val & ocr_voltage_support().val,
+    /// One CMD5 with the voltage window
+    /// self.card_type is updated once the card is powered up
+    /// Returns whether the card is powered up
+    pub(crate) fn sdio_cmd5_poll(&mut self, arg: u32) -> Result<bool, MciError> {
+        self.mci.send_command(SDIO_CMD5_SEND_OP_COND.into(), arg)?;
+        let resp = OcrRegister {
+            val: self.mci.get_response()?,
         };
-        let arg = arg.val;
-
-        // Wait until card is ready
-        // Timeout 1s = 400KHz / ((6+4)*8) cycles = 5000 retry
-        // TODO use proper delay?
-        for i in (0..5000).rev() {
-            if i == 0 {
-                return Err(MciError::Impl(ImplError::TimedOut));
-            }
-            self.mci.send_command(SDIO_CMD5_SEND_OP_COND.into(), arg)?;
-            let resp = OcrRegister {
-                val: self.mci.get_response()?,
-            };
-            if resp.card_powered_up_status() {
-                self.card_type.set_sdio(true);
-                if resp.memory_present() {
-                    self.card_type.set_sd(true);
-                }
-                break;
+        if resp.card_powered_up_status() {
+            self.card_type.set_sdio(true);
+            if resp.memory_present() {
+                self.card_type.set_sd(true);
             }
         }
-
-        Ok(())
+        Ok(resp.card_powered_up_status())
     }
 
     /// SDIO IO_RW_DIRECT command
     /// # Arguments
     /// * `direction` Read or write
     /// * `function` Function number
-    /// * `register_address` Register address
+    /// * `register_address` Register address, 17 bits
     /// * `read_after_write` Read after write flag
     /// * `write_data` Write data
     pub fn sdio_cmd52(
@@ -87,16 +119,47 @@ where
         read_after_write: bool,
         write_data: u8,
     ) -> Result<u8, MciError> {
+        Ok(self.sdio_cmd52_detailed(
+            direction,
+            function,
+            register_address,
+            read_after_write,
+            write_data,
+        )?)
+    }
+
+    /// SDIO IO_RW_DIRECT command, failing with the command and its R5 response flags
+    /// # Arguments
+    /// * `direction` Read or write
+    /// * `function` Function number
+    /// * `register_address` Register address, 17 bits
+    /// * `read_after_write` Read after write flag
+    /// * `write_data` Write data
+    pub fn sdio_cmd52_detailed(
+        &mut self,
+        direction: Direction,
+        function: FunctionSelection,
+        register_address: u32,
+        read_after_write: bool,
+        write_data: u8,
+    ) -> Result<u8, SdMmcCommandError> {
+        Self::sdio_check_address_range(register_address, false, 1)?;
+        let data_error = if direction == Direction::Read {
+            MciError::ReadError
+        } else {
+            MciError::WriteError
+        };
         let mut arg = Cmd52 { val: 0 };
         arg.set_write_data(write_data)
             .set_direction(direction)
             .set_function_number(function as u8)
             .set_read_after_write(read_after_write)
             .set_register_address(register_address);
-        self.mci
-            .send_command(SDIO_CMD52_IO_RW_DIRECT.into(), arg.val)?;
-        let resp = self.mci.get_response()? as u8;
-        Ok(resp)
+        let cmd = SDIO_CMD52_IO_RW_DIRECT.into();
+        if let Err(err) = self.mci.send_command(cmd, arg.val) {
+            return Err(self.sd_mmc_command_error(err, CommandContext::new(cmd, arg.val, None)));
+        }
+        self.sdio_check_r5(cmd, arg.val, data_error)
     }
 
     pub fn sdio_read_cia(
@@ -130,47 +193,98 @@ where
         Ok(ret)
     }
 
+    /// Address of the common CIS. The pointer is 3 bytes, LSB first
     pub fn sdio_cis_area_in_ccr_address(&mut self) -> Result<u32, MciError> {
-        self.sdio_read_cia_32bits(SDIO_CCCR_CIS_PTR)
+        Ok(self.sdio_read_cia_32bits(SDIO_CCCR_CIS_PTR)? & 0x00FF_FFFF)
     }
 
-    // TODO it says get max speed but it updates _self_. FIXME
-    /// Compute SDIO max transfer speed in Hz and update self.clock.
-    pub fn sdio_get_max_speed(&mut self) -> Result<(), MciError> {
-        let cis_address = self.sdio_cis_area_in_ccr_address()?;
-        let mut buf = [0u8; 6];
-        let mut addr = cis_address;
-
-        loop {
-            // Read a sample of CIA area
-            self.sdio_read_cia(addr, &mut buf, 4)?;
-            if buf[0] == SDIO_CISTPL_END {
-                return Err(MciError::CiaCouldNotFindTuple);
-            }
-            if buf[0] == SDIO_CISTPL_FUNCE && buf[2] == 0x0 {
-                break; // Fun0 tuple found
-            }
-            if buf[1] == 0 {
-                return Err(MciError::CiaCouldNotFindTuple);
-            }
+    /// Address of a function's CIS, taken from its FBR. The pointer is 3 bytes, LSB first
+    pub fn sdio_function_cis_address(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<u32, MciError> {
+        let mut pointer = [0u8; 4];
+        self.sdio_read_reg_bytes::<CisPointerRegister>(
+            SdioRegisterSpace::Fbr,
+            function,
+            &mut pointer[..3],
+        )?;
+        Ok(u32::from_le_bytes(pointer))
+    }
 
-            // Compute next address
-            addr += (buf[1] as u32) - 1;
-            if addr > (cis_address + 256) {
-                return Err(MciError::CiaCouldNotFindTuple);
-            }
+    /// Standard interface code and CSA support of a function, from its FBR
+    pub fn sdio_function_interface_code(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<CsaAndInterfaceCodeRegister, MciError> {
+        self.sdio_read_reg::<CsaAndInterfaceCodeRegister>(function)
+    }
+
+    /// Extended standard interface code of a function, from its FBR
+    pub fn sdio_function_extended_interface_code(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<ExtendedInterfaceCodeRegister, MciError> {
+        self.sdio_read_reg::<ExtendedInterfaceCodeRegister>(function)
+    }
+
+    /// Deadline of a wait polled with CMD52
+    pub(crate) fn sdio_cmd52_timeout(&self, timeout_ms: u32) -> Timeout {
+        self.sd_mmc_timeout(timeout_ms.saturating_mul(1000), SD_MMC_COMMAND_POLL_CYCLES)
+    }
+
+    /// Check that the function is one of the card's functions 1 to 7
+    pub(crate) fn sdio_check_function(&self, function: FunctionSelection) -> Result<(), MciError> {
+        if function == FunctionSelection::FunctionCia0
+            || function == FunctionSelection::MemoryInCard
+            || function as u8 > self.sdio_functions
+        {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        Ok(())
+    }
+
+    /// Maximum block size of a function, read from its FUNCE tuple
+    pub fn sdio_function_max_block_size(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<u16, MciError> {
+        if function == FunctionSelection::FunctionCia0 {
+            Ok(self.sdio_function0_extension()?.block_size)
+        } else {
+            Ok(self.sdio_function_extension(function)?.max_block_size)
         }
+    }
 
-        // Read all Fun0 tuple field: fn0_blk_size & max_tran_speed
-        addr -= 3;
-        self.sdio_read_cia(addr, &mut buf, 6)?;
+    /// Set the block size of a function, used by CMD53 block mode
+    /// self.sdio_block_sizes is updated
+    pub fn sdio_set_block_size(
+        &mut self,
+        function: FunctionSelection,
+        block_size: u16,
+    ) -> Result<(), MciError> {
+        // Function 0's block size lives in the CCCR at the same offset as in a FBR
+        let space = if function == FunctionSelection::FunctionCia0 {
+            SdioRegisterSpace::Cccr
+        } else {
+            SdioRegisterSpace::Fbr
+        };
+        self.sdio_write_reg_bytes::<BlockSize>(space, function, &block_size.to_le_bytes())?;
+        self.sdio_block_sizes[function as usize] = block_size;
+        Ok(())
+    }
 
-        let tplfe_max_tran_speed = if buf[5] > 0x32 {
+    // TODO it says get max speed but it updates _self_. FIXME
+    /// Compute SDIO max transfer speed in Hz and update self.clock.
+    pub fn sdio_get_max_speed(&mut self) -> Result<(), MciError> {
+        let max_transfer_speed = self.sdio_function0_extension()?.max_transfer_speed;
+
+        let tplfe_max_tran_speed = if max_transfer_speed > 0x32 {
             // Error on SDIO register, the high speed is not activated and the clock can't be more
             // than 25MHz. This error is present on specific SDIO card (H&D wireless card - HDG104 WiFi SIP)
             0x32
         } else {
-            buf[5]
+            max_transfer_speed
         } as usize;
 
         // Decode transfer speed in Hz
@@ -186,6 +300,103 @@ where
         Ok(())
     }
 
+    /// Initialize the SDIO card in MCI mode
+    /// This function runs the IO initialization and identification process (CMD5, CMD3, CMD7),
+    /// then it enables maximum bus width and transfer speed and sets the block size of every
+    /// function.
+    /// The memory side of a combo card is not initialized, sdio_install_combo does it.
+    /// self.state is Ready when done, Unusable if the install failed
+    pub fn sdio_install(&mut self) -> Result<(), MciError> {
+        self.state = CardState::Init;
+        match self.sdio_install_io() {
+            Ok(()) => {
+                self.state = CardState::Ready;
+                Ok(())
+            }
+            Err(err) => {
+                self.state = CardState::Unusable;
+                Err(err)
+            }
+        }
+    }
+
+    fn sdio_install_io(&mut self) -> Result<(), MciError> {
+        self.card_type.set_unknown();
+        self.sdio_reset_install_state();
+        // 1-bit bus width and low clock for initialization
+        self.sd_mmc_power_on()?;
+
+        // CMD0 - Reset all cards to idle state.
+        self.mci
+            .send_command(SDMMC_MCI_CMD0_GO_IDLE_STATE.into(), 0)?;
+
+        // CMD5 - Negotiate the operating voltage
+        self.sdio_send_operation_condition_command()?;
+        if !self.card_type.sdio() {
+            return Err(MciError::UnusableCard);
+        }
+        // The memory of a combo card stays uninitialized, see sdio_install_combo
+        self.card_type.set_sd(false);
+
+        // CMD3 - The card publishes its relative address
+        self.mci
+            .send_command(SD_CMD3_SEND_RELATIVE_ADDR.into(), 0)?;
+        self.rca = (self.mci.get_response()? >> 16) as u16;
+
+        // CMD7 - Select the card and put it into Command mode
+        self.mci
+            .send_command(SDMMC_CMD7_SELECT_CARD_CMD.into(), (self.rca as u32) << 16)?;
+
+        self.sdio_read_capabilities()?;
+        self.sdio_get_max_speed()?;
+        self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+
+        if BusWidth::_4BIT <= self.mci.get_bus_width(self.slot)? {
+            self.sdio_cmd52_switch_to_4_bus_width_mode()?;
+            self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        }
+        if self.mci.is_high_speed_capable()? {
+            self.sdio_cmd52_set_high_speed_mode()?;
+            self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        }
+
+        self.sdio_set_default_block_sizes()
+    }
+
+    /// Forget the state of a previously installed card
+    pub(crate) fn sdio_reset_install_state(&mut self) {
+        self.sdio_functions = 0;
+        self.sdio_multi_block_supported = false;
+        self.sdio_enabled_functions = IoEnableRegister { val: 0 };
+        self.sdio_enabled_interrupts = InterruptEnableRegister { val: 0 };
+    }
+
+    /// Check the CCCR revision and read the card capabilities of a selected card
+    pub(crate) fn sdio_read_capabilities(&mut self) -> Result<(), MciError> {
+        let revision =
+            self.sdio_read_reg::<CccrSdioRevisionRegister>(FunctionSelection::FunctionCia0)?;
+        if !revision.cccr_revision_supported() || !revision.sdio_specification_supported() {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+
+        let card_capability =
+            self.sdio_read_reg::<CardCapabilityRegister>(FunctionSelection::FunctionCia0)?;
+        self.sdio_multi_block_supported = card_capability.supports_multi_block();
+        Ok(())
+    }
+
+    /// Set the block size of every function to its maximum, up to SD_MMC_BLOCK_SIZE
+    pub(crate) fn sdio_set_default_block_sizes(&mut self) -> Result<(), MciError> {
+        for &function in &FunctionSelection::FUNCTIONS[..=self.sdio_functions as usize] {
+            let max_block_size = self.sdio_function_max_block_size(function)?;
+            let block_size = max_block_size.min(SD_MMC_BLOCK_SIZE as u16);
+            if block_size > 0 {
+                self.sdio_set_block_size(function, block_size)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Switch bus width to mode. self.bus_width is update
     /// Returns final bus_width
     /// SD memory cards always supports bus 4bit
@@ -194,26 +405,16 @@ where
     /// SDIO Low-Speed alone can support 4bit (Optional)
     pub fn sdio_cmd52_switch_to_4_bus_width_mode(&mut self) -> Result<BusWidth, MciError> {
         use crate::registers::sdio::cccr::bus_interface::BusWidth as SdioBusWidth;
-        let cccr_cap = CardCapabilityRegister {
-            val: self.sdio_cmd52(
-                Direction::Read,
-                FunctionSelection::FunctionCia0,
-                CardCapabilityRegister::address() as u32,
-                false,
-                0,
-            )?,
-        };
-        if !cccr_cap.low_speed_card_supports_4bit_mode() {
+        let cccr_cap =
+            self.sdio_read_reg::<CardCapabilityRegister>(FunctionSelection::FunctionCia0)?;
+        if cccr_cap.is_low_speed_card() && !cccr_cap.low_speed_card_supports_4bit_mode() {
             return Ok(BusWidth::_1BIT);
         }
-        let mut bus_ctrl = BusInterfaceControlRegister { val: 0 };
-        bus_ctrl.set_bus_width(SdioBusWidth::_4bit);
-        self.sdio_cmd52(
-            Direction::Write,
+        self.sdio_modify_reg(
             FunctionSelection::FunctionCia0,
-            BusInterfaceControlRegister::address() as u32,
-            true,
-            bus_ctrl.val,
+            |bus_ctrl: &mut BusInterfaceControlRegister| {
+                bus_ctrl.set_bus_width(SdioBusWidth::_4bit)
+            },
         )?;
         self.bus_width = BusWidth::_4BIT;
         Ok(BusWidth::_4BIT)
@@ -225,15 +426,8 @@ where
     ///
     /// Returns a true result if put in high speed mode, false if not possible
     pub fn sdio_cmd52_set_high_speed_mode(&mut self) -> Result<bool, MciError> {
-        let high_speed = HighSpeedRegister {
-            val: self.sdio_cmd52(
-                Direction::Read,
-                FunctionSelection::FunctionCia0,
-                HighSpeedRegister::address() as u32,
-                false,
-                0,
-            )?,
-        };
+        let high_speed =
+            self.sdio_read_reg::<HighSpeedRegister>(FunctionSelection::FunctionCia0)?;
 
         // Not supported, not a protocol error
         if !high_speed.supports_high_speed() {
@@ -242,54 +436,124 @@ where
 
         // TODO: Check if already in high speed using flag otherwise could lead to faulty state
 
-        let mut high_speed = HighSpeedRegister { val: 0 };
+        let mut high_speed = high_speed;
         high_speed.set_enable_high_speed(true);
-
-        self.sdio_cmd52(
-            Direction::Write,
-            FunctionSelection::FunctionCia0,
-            HighSpeedRegister::address() as u32,
-            true,
-            high_speed.val,
-        )?;
+        self.sdio_write_reg(FunctionSelection::FunctionCia0, high_speed)?;
         self.high_speed = true;
         self.clock *= 2;
         Ok(true)
     }
 
     /// CMD53 - SDIO IO_RW_EXTENDED command
-    /// This implementation support only the SDIO multi-byte transfer mode which is similar to the
-    /// single block transfer on memory.
+    /// In block mode, the block size is the one set for the function (self.sdio_block_sizes).
+    /// An infinite block transfer goes on until the function is aborted (ASx in the CCCR).
     /// Note: The SDIO block transfer mode is optional for SDIO card.
-    ///
+    /// # Arguments
+    /// * `direction` Read or write
+    /// * `function` Function number
+    /// * `register_address` Start address, 17 bits
+    /// * `increment_address` Increment the address after each byte, otherwise it is a FIFO
+    /// * `count` Byte or block count
+    /// * `access_block` If true - read_blocks/write_blocks must be used after this command
+    ///   Otherwise read_word/write_word must be used
     pub fn sdio_cmd53_io_rw_extended(
         &mut self,
         direction: Direction,
         function: FunctionSelection,
-        register_address: u16,
+        register_address: u32,
         increment_address: bool,
-        data_size: u16,
+        count: TransferCount,
         access_block: bool,
     ) -> Result<(), MciError> {
-        let command: u32 = if direction == Direction::Read {
-            SDIO_CMD53_IO_R_BLOCK_EXTENDED.into()
-        } else {
-            SDIO_CMD53_IO_W_BLOCK_EXTENDED.into()
+        let (command, block_size, block_amount): (u32, u16, u16) = match count {
+            TransferCount::Bytes(bytes) => {
+                if bytes == 0 || bytes > 512 {
+                    return Err(MciError::IncorrectDataSize);
+                }
+                let command = if direction == Direction::Read {
+                    SDIO_CMD53_IO_R_BYTE_EXTENDED.into()
+                } else {
+                    SDIO_CMD53_IO_W_BYTE_EXTENDED.into()
+                };
+                (command, bytes, 1)
+            }
+            TransferCount::Blocks(_) | TransferCount::InfiniteBlocks => {
+                let blocks = match count {
+                    TransferCount::Blocks(blocks) => blocks,
+                    _ => 0, // The host does not count the blocks
+                };
+                if count == TransferCount::Blocks(0) || blocks > SDIO_CMD53_MAX_BLOCKS {
+                    return Err(MciError::IncorrectDataSize);
+                }
+                if !self.sdio_multi_block_supported {
+                    return Err(MciError::Impl(ImplError::InvalidConfiguration));
+                }
+                let block_size = self.sdio_block_sizes[function as usize & 0x7];
+                if block_size == 0 {
+                    return Err(MciError::Impl(ImplError::InvalidConfiguration));
+                }
+                let command = if direction == Direction::Read {
+                    SDIO_CMD53_IO_R_BLOCK_EXTENDED.into()
+                } else {
+                    SDIO_CMD53_IO_W_BLOCK_EXTENDED.into()
+                };
+                (command, block_size, blocks)
+            }
         };
-        let mut arg = Cmd53::default();
 
-        if data_size == 0 || data_size > 512 {
-            return Err(MciError::IncorrectDataSize);
-        }
+        Self::sdio_check_address_range(
+            register_address,
+            increment_address,
+            block_size as usize * block_amount as usize,
+        )?;
 
-        arg.set_block_or_bytes_count(data_size % 512)
+        let data_error = if direction == Direction::Read {
+            MciError::ReadError
+        } else {
+            MciError::WriteError
+        };
+        let mut arg = Cmd53::default();
+        arg.set_transfer_count(count)
             .set_address(register_address)
             .set_op_code_increment_address(increment_address.into())
-            .set_block_mode(false)
             .set_function_number(function as u8)
             .set_direction(direction);
-        self.mci
-            .adtc_start(command, arg.val, data_size, 1, access_block)
+        if let Err(err) =
+            self.mci
+                .adtc_start(command, arg.val, block_size, block_amount, access_block)
+        {
+            return Err(self
+                .sd_mmc_command_error(err, CommandContext::new(command, arg.val, None))
+                .into());
+        }
+        self.sdio_check_r5(command, arg.val, data_error)?;
+        Ok(())
+    }
+
+    /// Largest transfer of a CMD53 in byte mode for the function
+    /// It is limited by the function's block size
+    pub fn sdio_max_byte_count(&self, function: FunctionSelection) -> u16 {
+        let block_size = self.sdio_block_sizes[function as usize & 0x7];
+        if block_size == 0 {
+            SDIO_CMD53_MAX_BYTES
+        } else {
+            block_size.min(SDIO_CMD53_MAX_BYTES)
+        }
+    }
+
+    /// Split a transfer of `length` bytes into the next CMD53 to send
+    /// Block mode is used for as many whole blocks as possible, the rest is sent in byte mode
+    pub fn sdio_next_transfer_count(
+        &self,
+        function: FunctionSelection,
+        length: usize,
+    ) -> TransferCount {
+        let block_size = self.sdio_block_sizes[function as usize & 0x7] as usize;
+        if self.sdio_multi_block_supported && block_size > 0 && length >= block_size {
+            TransferCount::Blocks((length / block_size).min(SDIO_CMD53_MAX_BLOCKS as usize) as u16)
+        } else {
+            TransferCount::Bytes(length.min(self.sdio_max_byte_count(function) as usize) as u16)
+        }
     }
 
     pub fn sdio_read_direct(
@@ -312,45 +576,176 @@ where
             .map(|_| ()) // TODO proper error
     }
 
+    /// Read any amount of data with CMD53, split into block mode and byte mode transfers
+    /// # Arguments
+    /// * `function` Function number
+    /// * `address` Start address, 17 bits
+    /// * `increment_address` Increment the address after each byte, otherwise it is a FIFO
+    /// * `destination` Buffer to fill
     pub fn sdio_read_extended(
         &mut self,
         function: FunctionSelection,
-        address: u16,
+        address: u32,
         increment_address: bool,
         destination: &mut [u8],
-        size: u16,
     ) -> Result<(), MciError> {
+        Self::sdio_check_address_range(address, increment_address, destination.len())?;
         self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
-        self.sdio_cmd53_io_rw_extended(
-            Direction::Read,
-            function,
-            address,
-            increment_address,
-            size,
-            true,
-        )?;
-        self.mci.read_blocks(destination, 1)?;
-        self.mci.wait_until_read_finished()
+        let mut position = 0usize;
+        while position < destination.len() {
+            let count = self.sdio_next_transfer_count(function, destination.len() - position);
+            let (length, blocks) = self.sdio_transfer_length(function, count);
+            let chunk_address = if increment_address {
+                address + position as u32
+            } else {
+                address
+            };
+            self.sdio_cmd53_io_rw_extended(
+                Direction::Read,
+                function,
+                chunk_address,
+                increment_address,
+                count,
+                true,
+            )?;
+            self.mci
+                .read_blocks(&mut destination[position..position + length], blocks)?;
+            self.mci.wait_until_read_finished()?;
+            position += length;
+        }
+        Ok(())
     }
 
+    /// Write any amount of data with CMD53, split into block mode and byte mode transfers
+    /// # Arguments
+    /// * `function` Function number
+    /// * `address` Start address, 17 bits
+    /// * `increment_address` Increment the address after each byte, otherwise it is a FIFO
+    /// * `source` Data to write
     pub fn sdio_write_extended(
         &mut self,
         function: FunctionSelection,
-        address: u16,
+        address: u32,
         increment_address: bool,
         source: &[u8],
-        size: u16,
     ) -> Result<(), MciError> {
+        Self::sdio_check_address_range(address, increment_address, source.len())?;
         self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
-        self.sdio_cmd53_io_rw_extended(
-            Direction::Write,
-            function,
-            address,
-            increment_address,
-            size,
-            true,
-        )?; // TODO proper error
-        self.mci.write_blocks(source, 1)?;
-        self.mci.wait_until_write_finished() // TODO proper error
+        let mut position = 0usize;
+        while position < source.len() {
+            let count = self.sdio_next_transfer_count(function, source.len() - position);
+            let (length, blocks) = self.sdio_transfer_length(function, count);
+            let chunk_address = if increment_address {
+                address + position as u32
+            } else {
+                address
+            };
+            self.sdio_cmd53_io_rw_extended(
+                Direction::Write,
+                function,
+                chunk_address,
+                increment_address,
+                count,
+                true,
+            )?;
+            self.mci
+                .write_blocks(&source[position..position + length], blocks)?;
+            self.mci.wait_until_write_finished()?;
+            position += length;
+        }
+        Ok(())
+    }
+
+    /// Check a transfer stays in the 17-bit address space of the function
+    /// An incrementing transfer must end in it as well, a FIFO only needs its address to be in it
+    /// # Arguments
+    /// * `address` Start address
+    /// * `increment_address` Whether the address is incremented after each byte
+    /// * `length` Bytes moved, 0 when not known
+    fn sdio_check_address_range(
+        address: u32,
+        increment_address: bool,
+        length: usize,
+    ) -> Result<(), MciError> {
+        let last = if increment_address {
+            (address as usize).checked_add(length.saturating_sub(1))
+        } else {
+            Some(address as usize)
+        };
+        match last {
+            Some(last) if last <= SDIO_MAX_ADDRESS as usize => Ok(()),
+            _ => Err(MciError::IncorrectDataSize),
+        }
+    }
+
+    /// Bytes moved by a CMD53 and the number of host blocks (read_blocks/write_blocks) it takes
+    fn sdio_transfer_length(
+        &self,
+        function: FunctionSelection,
+        count: TransferCount,
+    ) -> (usize, u16) {
+        match count {
+            TransferCount::Bytes(bytes) => (bytes as usize, 1),
+            TransferCount::Blocks(blocks) => (
+                blocks as usize * self.sdio_block_sizes[function as usize & 0x7] as usize,
+                blocks,
+            ),
+            TransferCount::InfiniteBlocks => (0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_mci::{test_card, TestCard, TestMci};
+
+    fn sdio_card(multi_block: bool, block_size: u16) -> TestCard {
+        let mut card = test_card(TestMci::new(0, &[]));
+        card.sdio_multi_block_supported = multi_block;
+        card.sdio_block_sizes[1] = block_size;
+        card
+    }
+
+    #[test]
+    fn whole_blocks_go_in_block_mode() {
+        let card = sdio_card(true, 64);
+        let function = FunctionSelection::Function1;
+        assert!(card.sdio_next_transfer_count(function, 64) == TransferCount::Blocks(1));
+        assert!(card.sdio_next_transfer_count(function, 200) == TransferCount::Blocks(3));
+        assert!(card.sdio_next_transfer_count(function, 8) == TransferCount::Bytes(8));
+    }
+
+    #[test]
+    fn block_count_is_capped() {
+        let card = sdio_card(true, 4);
+        let length = 4 * (SDIO_CMD53_MAX_BLOCKS as usize + 10);
+        assert!(
+            card.sdio_next_transfer_count(FunctionSelection::Function1, length)
+                == TransferCount::Blocks(SDIO_CMD53_MAX_BLOCKS)
+        );
+    }
+
+    #[test]
+    fn byte_mode_is_capped_by_the_block_size() {
+        let function = FunctionSelection::Function1;
+        let card = sdio_card(false, 64);
+        assert!(card.sdio_next_transfer_count(function, 200) == TransferCount::Bytes(64));
+        let card = sdio_card(false, 0);
+        assert!(
+            card.sdio_next_transfer_count(function, 1000)
+                == TransferCount::Bytes(SDIO_CMD53_MAX_BYTES)
+        );
+    }
+
+    #[test]
+    fn address_range_is_17_bits() {
+        type Card = TestCard;
+        assert!(Card::sdio_check_address_range(SDIO_MAX_ADDRESS, false, 1).is_ok());
+        assert!(Card::sdio_check_address_range(SDIO_MAX_ADDRESS + 1, false, 1).is_err());
+        assert!(Card::sdio_check_address_range(SDIO_MAX_ADDRESS - 9, true, 10).is_ok());
+        assert!(Card::sdio_check_address_range(SDIO_MAX_ADDRESS - 9, true, 11).is_err());
+        // A FIFO stays at its address
+        assert!(Card::sdio_check_address_range(SDIO_MAX_ADDRESS, false, 100).is_ok());
     }
 }
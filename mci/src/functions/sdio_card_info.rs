@@ -0,0 +1,183 @@
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::bus_interface::BusInterfaceControlRegister;
+use crate::registers::sdio::cccr::card_capability::CardCapabilityRegister;
+use crate::registers::sdio::cccr::cccr_sdio_revision::CccrSdioRevisionRegister;
+use crate::registers::sdio::cccr::driver_strength::DriverStrengthRegister;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cccr::high_speed::HighSpeedRegister;
+use crate::registers::sdio::cccr::interrupt_enable::InterruptEnableRegister;
+use crate::registers::sdio::cccr::io_enable::IoEnableRegister;
+use crate::registers::sdio::cccr::io_ready::IoReadyRegister;
+use crate::registers::sdio::cccr::power_control::PowerControlRegister;
+use crate::registers::sdio::cccr::sd_physical_specification::SdPhysicalSpecificationRegister;
+use crate::registers::sdio::cccr::uhs_support::UhsSupportRegister;
+use crate::registers::sdio::cis::function_extension::{
+    CisFunction0Extension, CisFunctionExtension,
+};
+use crate::registers::sdio::cis::function_id::CisFunctionId;
+use crate::registers::sdio::cis::manufacturer_id::CisManufacturerId;
+use crate::registers::sdio::cis::CisTuple;
+use crate::registers::sdio::fbr::csa_and_interface::CsaAndInterfaceCodeRegister;
+use crate::registers::sdio::fbr::extended_interface_code::ExtendedInterfaceCodeRegister;
+use crate::registers::sdio::fbr::power_control::PowerControlRegister as FunctionPowerControlRegister;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Summary of the CIS of the card or of a function
+/// A tuple is None when the CIS does not have it
+#[derive(Copy, Clone)]
+pub struct SdioCisSummary<E> {
+    /// Address of the CIS in the CIA
+    pub address: u32,
+    /// CISTPL_MANFID
+    pub manufacturer_id: Option<CisManufacturerId>,
+    /// CISTPL_FUNCID
+    pub function_id: Option<CisFunctionId>,
+    /// CISTPL_FUNCE
+    pub extension: Option<E>,
+}
+
+/// FBR and CIS of a function
+#[derive(Copy, Clone)]
+pub struct SdioFunctionReport {
+    /// Function 1 to 7
+    pub function: FunctionSelection,
+    /// Standard interface code and CSA support
+    pub interface: CsaAndInterfaceCodeRegister,
+    /// Extended standard interface code
+    pub extended_interface: ExtendedInterfaceCodeRegister,
+    /// High current mode support and selection
+    pub power_control: FunctionPowerControlRegister,
+    /// Block size set by the host
+    pub block_size: u16,
+    /// CIS of the function
+    pub cis: SdioCisSummary<CisFunctionExtension>,
+}
+
+/// Snapshot of the CCCR, of the FBR of every function and of their CIS
+/// UHS support and driver strength are reserved, read as 0, before CCCR revision 3.00
+#[derive(Copy, Clone)]
+pub struct SdioCardInfo {
+    pub revision: CccrSdioRevisionRegister,
+    pub physical_specification: SdPhysicalSpecificationRegister,
+    pub io_enable: IoEnableRegister,
+    pub io_ready: IoReadyRegister,
+    pub interrupt_enable: InterruptEnableRegister,
+    pub bus_interface: BusInterfaceControlRegister,
+    pub card_capability: CardCapabilityRegister,
+    pub power_control: PowerControlRegister,
+    pub high_speed: HighSpeedRegister,
+    pub uhs_support: UhsSupportRegister,
+    pub driver_strength: DriverStrengthRegister,
+    /// Common CIS
+    pub cis: SdioCisSummary<CisFunction0Extension>,
+    /// Number of functions, without function 0
+    pub functions: u8,
+    /// Functions 1 to 7, at index function - 1. None for functions the card does not have
+    pub function_reports: [Option<SdioFunctionReport>; 7],
+}
+
+impl SdioCardInfo {
+    /// Report of a function, None if the card does not have it
+    pub fn function(&self, function: FunctionSelection) -> Option<&SdioFunctionReport> {
+        match function as usize {
+            1..=7 => self.function_reports[function as usize - 1].as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Read the CCCR, the FBR of every function and their CIS
+    pub fn sdio_card_info(&mut self) -> Result<SdioCardInfo, MciError> {
+        let cia0 = FunctionSelection::FunctionCia0;
+        let mut function_reports = [None; 7];
+        for &function in &FunctionSelection::FUNCTIONS[1..=self.sdio_functions as usize] {
+            function_reports[function as usize - 1] = Some(self.sdio_function_report(function)?);
+        }
+        let cis_address = self.sdio_cis_area_in_ccr_address()?;
+        Ok(SdioCardInfo {
+            revision: self.sdio_read_reg(cia0)?,
+            physical_specification: self.sdio_read_reg(cia0)?,
+            io_enable: self.sdio_read_reg(cia0)?,
+            io_ready: self.sdio_read_reg(cia0)?,
+            interrupt_enable: self.sdio_read_reg(cia0)?,
+            bus_interface: self.sdio_read_reg(cia0)?,
+            card_capability: self.sdio_read_reg(cia0)?,
+            power_control: self.sdio_read_reg(cia0)?,
+            high_speed: self.sdio_read_reg(cia0)?,
+            uhs_support: self.sdio_read_reg(cia0)?,
+            driver_strength: self.sdio_read_reg(cia0)?,
+            cis: self.sdio_cis_summary(cis_address, |tuple| match tuple {
+                CisTuple::Function0Extension(extension) => Some(extension),
+                _ => None,
+            })?,
+            functions: self.sdio_functions,
+            function_reports,
+        })
+    }
+
+    /// Read the FBR and the CIS of a function
+    pub fn sdio_function_report(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<SdioFunctionReport, MciError> {
+        self.sdio_check_function(function)?;
+        let cis_address = self.sdio_function_cis_address(function)?;
+        Ok(SdioFunctionReport {
+            function,
+            interface: self.sdio_read_reg(function)?,
+            extended_interface: self.sdio_read_reg(function)?,
+            power_control: self.sdio_read_reg(function)?,
+            block_size: self.sdio_block_sizes[function as usize],
+            cis: self.sdio_cis_summary(cis_address, |tuple| match tuple {
+                CisTuple::FunctionExtension(extension) => Some(extension),
+                _ => None,
+            })?,
+        })
+    }
+
+    /// Walk a CIS once and keep the tuples of the summary
+    /// A chain running past the CIS area ends the walk
+    fn sdio_cis_summary<E, F>(
+        &mut self,
+        address: u32,
+        extension: F,
+    ) -> Result<SdioCisSummary<E>, MciError>
+    where
+        F: Fn(CisTuple) -> Option<E>,
+    {
+        let mut summary = SdioCisSummary {
+            address,
+            manufacturer_id: None,
+            function_id: None,
+            extension: None,
+        };
+        for tuple in self.sdio_cis_tuples_at(address) {
+            let tuple = match tuple {
+                Ok(tuple) => tuple,
+                Err(MciError::CiaCouldNotFindTuple) => break,
+                Err(err) => return Err(err),
+            };
+            match tuple {
+                CisTuple::ManufacturerId(id) => summary.manufacturer_id = Some(id),
+                CisTuple::FunctionId(id) => summary.function_id = Some(id),
+                tuple => {
+                    if summary.extension.is_none() {
+                        summary.extension = extension(tuple);
+                    }
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
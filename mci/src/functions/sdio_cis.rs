@@ -0,0 +1,236 @@
+use crate::command_arguments::sdio::cmd52::Direction;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cis::function_extension::{
+    CisFunction0Extension, CisFunctionExtension,
+};
+use crate::registers::sdio::cis::manufacturer_id::CisManufacturerId;
+use crate::registers::sdio::cis::tuple_data::CisTupleData;
+use crate::registers::sdio::cis::{CisTuple, CISTPL_END, CISTPL_NULL};
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Last address of the CIS area in the CIA
+pub const SDIO_CIS_AREA_END: u32 = 0x17FFF;
+/// Link value that marks the end of the tuple chain
+pub const SDIO_CIS_LINK_END: u8 = 0xFF;
+
+/// Iterator over the tuples of a Card Information Structure (CIS)
+/// The tuple chain is followed until CISTPL_END or the end of the CIS area. Tuples are read with
+/// CMD52 since the CIS can live above the 16 bit addresses reachable with CMD53.
+pub struct CisTuples<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    card: &'a mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    address: u32,
+    done: bool,
+}
+
+impl<'a, MCI, WP, DETECT, POWER, CLOCK> CisTuples<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    fn read_tuple(&mut self) -> Result<Option<CisTuple>, MciError> {
+        let code = loop {
+            if self.address > SDIO_CIS_AREA_END {
+                return Err(MciError::CiaCouldNotFindTuple);
+            }
+            let code = self.card.sdio_cmd52(
+                Direction::Read,
+                FunctionSelection::FunctionCia0,
+                self.address,
+                false,
+                0,
+            )?;
+            match code {
+                CISTPL_END => return Ok(None),
+                // The null tuple has no link byte
+                CISTPL_NULL => self.address += 1,
+                code => break code,
+            }
+        };
+
+        let length = self.card.sdio_cmd52(
+            Direction::Read,
+            FunctionSelection::FunctionCia0,
+            self.address + 1,
+            false,
+            0,
+        )?;
+        if length == SDIO_CIS_LINK_END {
+            return Ok(None);
+        }
+
+        let mut tuple = CisTupleData {
+            code,
+            length,
+            address: self.address,
+            data: [0u8; 255],
+        };
+        self.card
+            .sdio_read_cia(self.address + 2, &mut tuple.data, length as usize)?;
+        self.address += (length as u32) + 2;
+        Ok(Some(tuple.into()))
+    }
+}
+
+impl<'a, MCI, WP, DETECT, POWER, CLOCK> Iterator for CisTuples<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    type Item = Result<CisTuple, MciError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_tuple() {
+            Ok(Some(tuple)) => Some(Ok(tuple)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Iterate the tuples of a CIS starting at `address`
+    pub fn sdio_cis_tuples_at(
+        &mut self,
+        address: u32,
+    ) -> CisTuples<'_, MCI, WP, DETECT, POWER, CLOCK> {
+        CisTuples {
+            card: self,
+            address,
+            done: false,
+        }
+    }
+
+    /// Iterate the tuples of a function's CIS
+    /// Function 0 is the common CIS, pointed to by the CCCR. The other functions' CIS are pointed
+    /// to by their FBR
+    pub fn sdio_cis_tuples(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<CisTuples<'_, MCI, WP, DETECT, POWER, CLOCK>, MciError> {
+        let address = if function == FunctionSelection::FunctionCia0 {
+            self.sdio_cis_area_in_ccr_address()?
+        } else {
+            self.sdio_function_cis_address(function)?
+        };
+        Ok(self.sdio_cis_tuples_at(address))
+    }
+
+    /// Manufacturer and card identification (CISTPL_MANFID) of a function's CIS
+    pub fn sdio_manufacturer_id(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<CisManufacturerId, MciError> {
+        for tuple in self.sdio_cis_tuples(function)? {
+            if let CisTuple::ManufacturerId(id) = tuple? {
+                return Ok(id);
+            }
+        }
+        Err(MciError::CiaCouldNotFindTuple)
+    }
+
+    /// Function 0 extension tuple of the common CIS
+    pub fn sdio_function0_extension(&mut self) -> Result<CisFunction0Extension, MciError> {
+        for tuple in self.sdio_cis_tuples(FunctionSelection::FunctionCia0)? {
+            if let CisTuple::Function0Extension(extension) = tuple? {
+                return Ok(extension);
+            }
+        }
+        Err(MciError::CiaCouldNotFindTuple)
+    }
+
+    /// Function extension tuple of a function's (1 to 7) CIS
+    pub fn sdio_function_extension(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<CisFunctionExtension, MciError> {
+        for tuple in self.sdio_cis_tuples(function)? {
+            if let CisTuple::FunctionExtension(extension) = tuple? {
+                return Ok(extension);
+            }
+        }
+        Err(MciError::CiaCouldNotFindTuple)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_mci::{test_card, TestMci};
+
+    #[test]
+    fn null_tuples_are_skipped_until_the_end_tuple() {
+        let cis = [
+            0x00, 0x00, 0x20, 0x04, 0x96, 0x02, 0x34, 0x12, 0x80, 0x02, 0xAA, 0xBB, 0xFF,
+        ];
+        let mut card = test_card(TestMci::new(0x1000, &cis));
+        let mut tuples = card.sdio_cis_tuples_at(0x1000);
+        match tuples.next() {
+            Some(Ok(CisTuple::ManufacturerId(id))) => {
+                assert_eq!(id.manufacturer, 0x0296);
+                assert_eq!(id.card, 0x1234);
+            }
+            _ => panic!("expected the CISTPL_MANFID tuple"),
+        }
+        match tuples.next() {
+            Some(Ok(CisTuple::Raw(tuple))) => {
+                assert_eq!(tuple.code, 0x80);
+                assert_eq!(tuple.address, 0x1008);
+                assert_eq!(tuple.body(), &[0xAA, 0xBB]);
+            }
+            _ => panic!("expected the vendor tuple"),
+        }
+        assert!(tuples.next().is_none());
+        assert!(tuples.next().is_none());
+    }
+
+    #[test]
+    fn end_link_stops_the_chain() {
+        let cis = [0x80, SDIO_CIS_LINK_END, 0x20, 0x04, 0x96, 0x02, 0x34, 0x12];
+        let mut card = test_card(TestMci::new(0x1000, &cis));
+        assert!(card.sdio_cis_tuples_at(0x1000).next().is_none());
+    }
+
+    #[test]
+    fn walk_past_the_cis_area_fails() {
+        let mut card = test_card(TestMci::new(SDIO_CIS_AREA_END, &[CISTPL_NULL]));
+        let mut tuples = card.sdio_cis_tuples_at(SDIO_CIS_AREA_END);
+        assert!(matches!(
+            tuples.next(),
+            Some(Err(MciError::CiaCouldNotFindTuple))
+        ));
+        assert!(tuples.next().is_none());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::card_state::CardState;
+use crate::card_version::SdCardVersion;
+use crate::command_arguments::mmc::BusWidth;
+use crate::commands::{
+    SDMMC_CMD16_SET_BLOCKLEN, SDMMC_CMD2_ALL_SEND_CID, SDMMC_CMD7_SELECT_CARD_CMD,
+    SDMMC_MCI_CMD0_GO_IDLE_STATE, SD_CMD3_SEND_RELATIVE_ADDR,
+};
+use crate::functions::sdmmc::SD_MMC_BLOCK_SIZE;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cccr::high_speed::HighSpeedRegister;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Initialize an SDIO card in MCI mode, with the memory side of a combo card
+    /// The IO and the memory are identified together and share the RCA. The bus width and the
+    /// speed are only raised when both sides support them, the clock is the slowest of both.
+    /// Without memory this is the same as sdio_install.
+    /// Afterwards self.card_type has sd and sdio set for a combo card: the memory block API and
+    /// the SDIO function API both work on the card.
+    /// self.state is Ready when done, Unusable if the install failed
+    pub fn sdio_install_combo(&mut self) -> Result<(), MciError> {
+        self.state = CardState::Init;
+        match self.sdio_install_combo_card() {
+            Ok(()) => {
+                self.state = CardState::Ready;
+                Ok(())
+            }
+            Err(err) => {
+                self.state = CardState::Unusable;
+                Err(err)
+            }
+        }
+    }
+
+    /// Whether both the IO and the memory of a combo card are installed
+    pub fn sdio_is_combo(&self) -> bool {
+        self.card_type.sdio() && self.card_type.sd()
+    }
+
+    fn sdio_install_combo_card(&mut self) -> Result<(), MciError> {
+        let v2 = self.sdio_combo_reset()?;
+        // CMD5 - Negotiate the IO operating voltage, and tell if there is a memory
+        self.sdio_send_operation_condition_command()?;
+        if !self.card_type.sdio() {
+            return Err(MciError::UnusableCard);
+        }
+        if self.card_type.sd() {
+            // ACMD41 - The memory gets the same voltage window as the IO
+            self.sd_mci_operations_conditions(v2)?;
+        }
+        self.sdio_combo_install_powered_up()
+    }
+
+    /// Reset both sides of the card to their idle state
+    /// Returns whether the memory is v2, from CMD8
+    pub(crate) fn sdio_combo_reset(&mut self) -> Result<bool, MciError> {
+        self.card_type.set_unknown();
+        self.sdio_reset_install_state();
+        // 1-bit bus width and low clock for initialization
+        self.sd_mmc_power_on()?;
+        self.sdio_combo_reset_powered()
+    }
+
+    /// Reset both sides of the powered on card, see sdio_combo_reset
+    pub(crate) fn sdio_combo_reset_powered(&mut self) -> Result<bool, MciError> {
+        // The IO portion is not reset by CMD0
+        self.sdio_reset()?;
+        // CMD0 - Reset the memory to idle state
+        self.mci
+            .send_command(SDMMC_MCI_CMD0_GO_IDLE_STATE.into(), 0)?;
+        // CMD8 must come before the first ACMD41 of a v2 memory
+        self.sd_cmd8_is_v2()
+    }
+
+    /// Identify the powered up IO and memory, then raise the bus width and the speed
+    pub(crate) fn sdio_combo_install_powered_up(&mut self) -> Result<(), MciError> {
+        let memory = self.card_type.sd();
+        if memory {
+            // CMD2 - Put the memory in identification state
+            self.mci.send_command(SDMMC_CMD2_ALL_SEND_CID.into(), 0)?;
+        }
+
+        // CMD3 - Both sides publish the same relative address
+        self.mci
+            .send_command(SD_CMD3_SEND_RELATIVE_ADDR.into(), 0)?;
+        self.rca = (self.mci.get_response()? >> 16) as u16;
+
+        let mut memory_clock = u32::MAX;
+        if memory {
+            // CMD9 - Memory CSD, gives the capacity and the memory clock
+            self.sd_mmc_cmd9_mci()?;
+            self.sd_decode_csd()?;
+            memory_clock = self.clock;
+        }
+
+        // CMD7 - Select the card and put it into transfer mode
+        self.mci
+            .send_command(SDMMC_CMD7_SELECT_CARD_CMD.into(), (self.rca as u32) << 16)?;
+
+        if memory {
+            // ACMD51 - Memory version and CMD23 support
+            self.sd_acmd51()?;
+        }
+        self.sdio_read_capabilities()?;
+        self.sdio_get_max_speed()?;
+        self.clock = self.clock.min(memory_clock);
+        self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+
+        if BusWidth::_4BIT <= self.mci.get_bus_width(self.slot)? {
+            // A combo card is a full speed SDIO card, 4-bit is mandatory for both sides
+            if self.sdio_cmd52_switch_to_4_bus_width_mode()? == BusWidth::_4BIT && memory {
+                self.sd_acmd6_set_data_bus_width_to_4_bits()?;
+            }
+            self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+        }
+
+        if self.mci.is_high_speed_capable()? {
+            let clock = self.clock;
+            let io_high_speed = self
+                .sdio_read_reg::<HighSpeedRegister>(FunctionSelection::FunctionCia0)?
+                .supports_high_speed();
+            // CMD6 is only supported from SD 1.10
+            let memory_high_speed = !memory
+                || (usize::from(self.version) > SdCardVersion::Sd1d0 as usize
+                    && io_high_speed
+                    && self.sd_cmd6_set_to_high_speed_mode()?);
+            if memory_high_speed && io_high_speed && self.sdio_cmd52_set_high_speed_mode()? {
+                // Each side doubled the clock
+                self.clock = clock * 2;
+                self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
+            }
+        }
+
+        if memory {
+            self.mci
+                .send_command(SDMMC_CMD16_SET_BLOCKLEN.into(), SD_MMC_BLOCK_SIZE)?;
+        }
+        self.sdio_set_default_block_sizes()
+    }
+}
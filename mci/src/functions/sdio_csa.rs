@@ -0,0 +1,157 @@
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::fbr::csa_and_interface::CsaAndInterfaceCodeRegister;
+use crate::registers::sdio::fbr::csa_pointer::CsaPointerRegister;
+use crate::registers::sdio::fbr::fifo_to_csa::FifoToCsaReadWriteRegister;
+use crate::registers::sdio::sdio_register::SdioRegisterSpace;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Code Storage Area of a function, accessed as byte addressable storage
+/// Data goes through the CSA window of the function's FBR with fixed address CMD53
+pub struct SdioCsa<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    card: &'a mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    function: FunctionSelection,
+    size: u32,
+    write_protected: bool,
+}
+
+impl<'a, MCI, WP, DETECT, POWER, CLOCK> SdioCsa<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Function the CSA belongs to
+    pub fn function(&self) -> FunctionSelection {
+        self.function
+    }
+
+    /// Size of the CSA in bytes (TPLFE_CSA_SIZE)
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Whether the CSA is write protected (TPLFE_CSA_PROPERTY)
+    pub fn write_protected(&self) -> bool {
+        self.write_protected
+    }
+
+    /// Read from the CSA
+    /// # Arguments
+    /// * `offset` Byte offset in the CSA
+    /// * `destination` Buffer to fill
+    pub fn read(&mut self, offset: u32, destination: &mut [u8]) -> Result<(), MciError> {
+        self.check_range(offset, destination.len())?;
+        if destination.is_empty() {
+            return Ok(());
+        }
+        self.card.sdio_set_csa_pointer(self.function, offset)?;
+        let window = self.window_address()?;
+        self.card
+            .sdio_read_extended(FunctionSelection::FunctionCia0, window, false, destination)
+    }
+
+    /// Write to the CSA
+    /// # Arguments
+    /// * `offset` Byte offset in the CSA
+    /// * `source` Data to write
+    pub fn write(&mut self, offset: u32, source: &[u8]) -> Result<(), MciError> {
+        if self.write_protected {
+            return Err(MciError::WriteProtected);
+        }
+        self.check_range(offset, source.len())?;
+        if source.is_empty() {
+            return Ok(());
+        }
+        self.card.sdio_set_csa_pointer(self.function, offset)?;
+        let window = self.window_address()?;
+        self.card
+            .sdio_write_extended(FunctionSelection::FunctionCia0, window, false, source)
+    }
+
+    fn window_address(&self) -> Result<u32, MciError> {
+        self.card.sdio_area_address(
+            SdioRegisterSpace::Fbr,
+            self.function,
+            FifoToCsaReadWriteRegister::address(),
+        )
+    }
+
+    fn check_range(&self, offset: u32, length: usize) -> Result<(), MciError> {
+        if offset as u64 + length as u64 > self.size as u64 {
+            return Err(MciError::IncorrectDataSize);
+        }
+        Ok(())
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Whether a function has a CSA
+    pub fn sdio_csa_supported(&mut self, function: FunctionSelection) -> Result<bool, MciError> {
+        self.sdio_check_function(function)?;
+        Ok(self
+            .sdio_function_interface_code(function)?
+            .supports_code_storage_area())
+    }
+
+    /// Enable the CSA of a function and access it
+    /// The size and write protection come from the function's FUNCE tuple
+    pub fn sdio_csa(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<SdioCsa<'_, MCI, WP, DETECT, POWER, CLOCK>, MciError> {
+        self.sdio_check_function(function)?;
+        if !self.sdio_csa_supported(function)? {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        let extension = self.sdio_function_extension(function)?;
+        self.sdio_modify_reg(function, |r: &mut CsaAndInterfaceCodeRegister| {
+            r.set_enable_code_storage_area(true)
+        })?;
+        Ok(SdioCsa {
+            card: self,
+            function,
+            size: extension.csa_size,
+            write_protected: !extension.csa_writable(),
+        })
+    }
+
+    /// Set the 24 bit CSA pointer of a function, LSB first
+    pub fn sdio_set_csa_pointer(
+        &mut self,
+        function: FunctionSelection,
+        pointer: u32,
+    ) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        if pointer > 0x00FF_FFFF {
+            return Err(MciError::IncorrectDataSize);
+        }
+        let csa_pointer = CsaPointerRegister { val: pointer };
+        self.sdio_write_reg_bytes::<CsaPointerRegister>(
+            SdioRegisterSpace::Fbr,
+            function,
+            &[csa_pointer.lsb(), csa_pointer.mid(), csa_pointer.msb()],
+        )
+    }
+}
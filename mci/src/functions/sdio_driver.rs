@@ -0,0 +1,350 @@
+use crate::command_arguments::sdio::cmd52::Direction;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cccr::interrupt_pending::InterruptPendingRegister;
+use crate::registers::sdio::cis::manufacturer_id::CisManufacturerId;
+use crate::registers::sdio::fbr::csa_and_interface::SdioInterfaceFunctionCode;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Identification a driver binds to
+#[derive(Copy, Clone, PartialEq)]
+pub enum SdioDeviceId {
+    /// SDIO standard interface code of the FBR
+    Interface(SdioInterfaceFunctionCode),
+    /// Extended SDIO standard interface code of the FBR, when the interface code is CheckExt
+    ExtendedInterface(u8),
+    /// Manufacturer and card identification of the CIS (CISTPL_MANFID)
+    Manufacturer { manufacturer: u16, card: u16 },
+}
+
+/// Identification of a function, used to match drivers
+#[derive(Copy, Clone)]
+pub struct SdioFunctionInfo {
+    /// Function 1 to 7
+    pub function: FunctionSelection,
+    /// SDIO standard interface code
+    pub interface_code: SdioInterfaceFunctionCode,
+    /// Extended SDIO standard interface code
+    pub extended_interface_code: u8,
+    /// CISTPL_MANFID of the function's CIS, or of the common CIS when the function has none
+    pub manufacturer_id: Option<CisManufacturerId>,
+}
+
+impl SdioFunctionInfo {
+    /// Whether the function matches the identification
+    pub fn matches(&self, id: &SdioDeviceId) -> bool {
+        match *id {
+            SdioDeviceId::Interface(code) => self.interface_code == code,
+            SdioDeviceId::ExtendedInterface(code) => {
+                self.interface_code == SdioInterfaceFunctionCode::CheckExt
+                    && self.extended_interface_code == code
+            }
+            SdioDeviceId::Manufacturer { manufacturer, card } => {
+                self.manufacturer_id == Some(CisManufacturerId { manufacturer, card })
+            }
+        }
+    }
+}
+
+/// Access to one function of a card. CMD52 and CMD53 only reach this function
+pub struct SdioFunction<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    card: &'a mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    function: FunctionSelection,
+}
+
+impl<'a, MCI, WP, DETECT, POWER, CLOCK> SdioFunction<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Function number of this handle
+    pub fn function(&self) -> FunctionSelection {
+        self.function
+    }
+
+    /// Block size set for the function
+    pub fn block_size(&self) -> u16 {
+        self.card.sdio_block_sizes[self.function as usize]
+    }
+
+    /// Set the block size of the function
+    pub fn set_block_size(&mut self, block_size: u16) -> Result<(), MciError> {
+        self.card.sdio_set_block_size(self.function, block_size)
+    }
+
+    /// Read a register of the function (CMD52)
+    pub fn read_direct(&mut self, address: u32) -> Result<u8, MciError> {
+        self.card
+            .sdio_cmd52(Direction::Read, self.function, address, false, 0)
+    }
+
+    /// Write a register of the function (CMD52)
+    pub fn write_direct(&mut self, address: u32, data: u8) -> Result<(), MciError> {
+        self.card
+            .sdio_cmd52(Direction::Write, self.function, address, false, data)
+            .map(|_| ())
+    }
+
+    /// Write a register of the function and read it back (CMD52 with RAW)
+    pub fn write_read_direct(&mut self, address: u32, data: u8) -> Result<u8, MciError> {
+        self.card
+            .sdio_cmd52(Direction::Write, self.function, address, true, data)
+    }
+
+    /// Read any amount of data from the function (CMD53)
+    pub fn read_extended(
+        &mut self,
+        address: u32,
+        increment_address: bool,
+        destination: &mut [u8],
+    ) -> Result<(), MciError> {
+        self.card
+            .sdio_read_extended(self.function, address, increment_address, destination)
+    }
+
+    /// Write any amount of data to the function (CMD53)
+    pub fn write_extended(
+        &mut self,
+        address: u32,
+        increment_address: bool,
+        source: &[u8],
+    ) -> Result<(), MciError> {
+        self.card
+            .sdio_write_extended(self.function, address, increment_address, source)
+    }
+
+    /// Enable the function and wait until it is ready
+    pub fn enable(&mut self, timeout_ms: Option<u32>) -> Result<(), MciError> {
+        self.card.sdio_enable_function(self.function, timeout_ms)
+    }
+
+    /// Disable the function
+    pub fn disable(&mut self) -> Result<(), MciError> {
+        self.card.sdio_disable_function(self.function)
+    }
+
+    /// Enable the interrupt of the function
+    pub fn enable_interrupt(&mut self) -> Result<(), MciError> {
+        self.card.sdio_enable_interrupt(self.function)
+    }
+
+    /// Disable the interrupt of the function
+    pub fn disable_interrupt(&mut self) -> Result<(), MciError> {
+        self.card.sdio_disable_interrupt(self.function)
+    }
+
+    /// Whether the host sees the card interrupt, raised by this function or another one
+    pub fn card_interrupt_pending(&mut self) -> Result<bool, MciError> {
+        self.card.mci.is_sdio_interrupt_pending()
+    }
+}
+
+/// Driver of an SDIO function
+pub trait SdioFunctionDriver<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Identifications the driver supports
+    fn ids(&self) -> &[SdioDeviceId];
+
+    /// Check a matching function before attaching to it. Returns false to let another driver bind
+    fn probe(
+        &mut self,
+        _function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+        _info: &SdioFunctionInfo,
+    ) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
+    /// Bind to the function. The function is usually enabled here
+    fn attach(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>;
+
+    /// Unbind from the function
+    fn detach(
+        &mut self,
+        _function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    /// The function has an interrupt pending. The driver must clear its source
+    fn interrupt(
+        &mut self,
+        _function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        Ok(())
+    }
+}
+
+/// Binds drivers to the functions of a card
+/// The first driver that matches and probes a function is attached to it
+pub struct SdioDriverRegistry<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    drivers: &'a mut [&'a mut dyn SdioFunctionDriver<MCI, WP, DETECT, POWER, CLOCK>],
+    /// Index of the driver bound to each function, function 0 included
+    bindings: [Option<usize>; 8],
+}
+
+impl<'a, MCI, WP, DETECT, POWER, CLOCK> SdioDriverRegistry<'a, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Create a registry. Drivers are matched in order
+    pub fn new(
+        drivers: &'a mut [&'a mut dyn SdioFunctionDriver<MCI, WP, DETECT, POWER, CLOCK>],
+    ) -> Self {
+        SdioDriverRegistry {
+            drivers,
+            bindings: [None; 8],
+        }
+    }
+
+    /// Index of the driver bound to a function
+    pub fn driver_index(&self, function: FunctionSelection) -> Option<usize> {
+        self.bindings[function as usize & 0x7]
+    }
+
+    /// Match and attach drivers to every unbound function of an installed card
+    pub fn bind(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        for &function in &FunctionSelection::FUNCTIONS[1..=card.sdio_functions as usize] {
+            if self.bindings[function as usize].is_some() {
+                continue;
+            }
+            let info = card.sdio_function_info(function)?;
+            for (index, driver) in self.drivers.iter_mut().enumerate() {
+                if !driver.ids().iter().any(|id| info.matches(id)) {
+                    continue;
+                }
+                let mut handle = SdioFunction {
+                    card: &mut *card,
+                    function,
+                };
+                if driver.probe(&mut handle, &info)? {
+                    driver.attach(&mut handle)?;
+                    self.bindings[function as usize] = Some(index);
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Detach the drivers from every function
+    pub fn unbind(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        for &function in &FunctionSelection::FUNCTIONS[1..] {
+            if let Some(index) = self.bindings[function as usize].take() {
+                let mut handle = SdioFunction { card, function };
+                self.drivers[index].detach(&mut handle)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Call the interrupt hook of the drivers whose function has an interrupt pending
+    /// To be called when the host reports the card interrupt (mci.is_sdio_interrupt_pending)
+    /// Returns the pending interrupts read from the card
+    pub fn dispatch_interrupts(
+        &mut self,
+        card: &mut MciCard<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<InterruptPendingRegister, MciError> {
+        let pending = card.sdio_interrupts_pending()?;
+        for &function in &FunctionSelection::FUNCTIONS[1..] {
+            if !pending.function_interrupt_pending(function) {
+                continue;
+            }
+            if let Some(index) = self.bindings[function as usize] {
+                let mut handle = SdioFunction { card, function };
+                self.drivers[index].interrupt(&mut handle)?;
+            }
+        }
+        Ok(pending)
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Handle to one function, to access it outside of the driver hooks
+    pub fn sdio_function(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<SdioFunction<'_, MCI, WP, DETECT, POWER, CLOCK>, MciError> {
+        self.sdio_check_function(function)?;
+        Ok(SdioFunction {
+            card: self,
+            function,
+        })
+    }
+
+    /// Identification of a function, read from its FBR and CIS
+    pub fn sdio_function_info(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<SdioFunctionInfo, MciError> {
+        self.sdio_check_function(function)?;
+        let interface_code = self
+            .sdio_function_interface_code(function)?
+            .interface_code();
+        let extended_interface_code = self
+            .sdio_function_extended_interface_code(function)?
+            .extended_interface_code();
+        let manufacturer_id = match self.sdio_manufacturer_id(function) {
+            Ok(id) => Some(id),
+            Err(MciError::CiaCouldNotFindTuple) => {
+                match self.sdio_manufacturer_id(FunctionSelection::FunctionCia0) {
+                    Ok(id) => Some(id),
+                    Err(MciError::CiaCouldNotFindTuple) => None,
+                    Err(err) => return Err(err),
+                }
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(SdioFunctionInfo {
+            function,
+            interface_code,
+            extended_interface_code,
+            manufacturer_id,
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cccr::io_enable::IoEnableRegister;
+use crate::registers::sdio::cccr::io_ready::IoReadyRegister;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Enable timeout used when a function's CIS does not give one, in ms
+pub const SDIO_DEFAULT_ENABLE_TIMEOUT_MS: u32 = 1000;
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Enable a function (IOEx) and wait until it is ready (IORx)
+    /// self.sdio_enabled_functions is updated
+    /// # Arguments
+    /// * `function` Function 1 to 7
+    /// * `timeout_ms` Time to wait for the function to become ready. If None, the function's
+    ///   TPLFE_ENABLE_TIMEOUT_VAL is used
+    pub fn sdio_enable_function(
+        &mut self,
+        function: FunctionSelection,
+        timeout_ms: Option<u32>,
+    ) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        let timeout_ms = match timeout_ms {
+            Some(timeout_ms) => timeout_ms,
+            None => self.sdio_function_enable_timeout_ms(function)?,
+        };
+        self.sdio_write_io_enable(function, true)?;
+
+        let mut timeout = self.sdio_cmd52_timeout(timeout_ms);
+        loop {
+            let io_ready =
+                self.sdio_read_reg::<IoReadyRegister>(FunctionSelection::FunctionCia0)?;
+            if io_ready.function_ready(function) {
+                return Ok(());
+            }
+            if timeout.expired(self.monotonic_clock.as_ref()) {
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
+        }
+    }
+
+    /// Disable a function (IOEx)
+    /// self.sdio_enabled_functions is updated
+    pub fn sdio_disable_function(&mut self, function: FunctionSelection) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        self.sdio_write_io_enable(function, false)
+    }
+
+    /// Whether the function was enabled with sdio_enable_function
+    pub fn sdio_function_enabled(&self, function: FunctionSelection) -> bool {
+        self.sdio_check_function(function).is_ok()
+            && self.sdio_enabled_functions.function_enabled(function)
+    }
+
+    /// Time a function needs to become ready after being enabled, from its FUNCE tuple, in ms
+    pub fn sdio_function_enable_timeout_ms(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<u32, MciError> {
+        let enable_timeout = match self.sdio_function_extension(function) {
+            Ok(extension) => extension.enable_timeout as u32 * 10,
+            Err(MciError::CiaCouldNotFindTuple) => 0,
+            Err(err) => return Err(err),
+        };
+        Ok(if enable_timeout == 0 {
+            SDIO_DEFAULT_ENABLE_TIMEOUT_MS
+        } else {
+            enable_timeout
+        })
+    }
+
+    /// Read-modify-write of the function's IOEx bit, verified with read after write
+    fn sdio_write_io_enable(
+        &mut self,
+        function: FunctionSelection,
+        enabled: bool,
+    ) -> Result<(), MciError> {
+        match self.sdio_modify_reg(
+            FunctionSelection::FunctionCia0,
+            |io_enable: &mut IoEnableRegister| io_enable.set_function_enabled(function, enabled),
+        ) {
+            Ok(io_enable) => {
+                self.sdio_enabled_functions = io_enable;
+                Ok(())
+            }
+            Err(err) => {
+                // Keep following the card when the write did not take
+                if let Ok(current) = self.sdio_read_reg(FunctionSelection::FunctionCia0) {
+                    self.sdio_enabled_functions = current;
+                }
+                Err(err)
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::command_arguments::mmc::BusWidth;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::card_capability::CardCapabilityRegister;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cccr::interrupt_enable::InterruptEnableRegister;
+use crate::registers::sdio::cccr::interrupt_pending::InterruptPendingRegister;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Handler called when a function has an interrupt pending
+/// The handler must clear the interrupt source of the function, otherwise the card keeps
+/// signalling it
+pub type SdioInterruptHandler<MCI, WP, DETECT, POWER, CLOCK> =
+    fn(&mut MciCard<MCI, WP, DETECT, POWER, CLOCK>, FunctionSelection);
+
+/// Interrupt handler of each function, function 0 included
+pub type SdioInterruptHandlers<MCI, WP, DETECT, POWER, CLOCK> =
+    [Option<SdioInterruptHandler<MCI, WP, DETECT, POWER, CLOCK>>; 8];
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Register the interrupt handler of a function. None removes it
+    /// # Arguments
+    /// * `function` Function 1 to 7
+    /// * `handler` Called by sdio_dispatch_interrupts when the function has an interrupt pending
+    pub fn sdio_register_interrupt_handler(
+        &mut self,
+        function: FunctionSelection,
+        handler: Option<SdioInterruptHandler<MCI, WP, DETECT, POWER, CLOCK>>,
+    ) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        self.sdio_interrupt_handlers[function as usize] = handler;
+        Ok(())
+    }
+
+    /// Enable the interrupt of a function (IENx) and the interrupt master (IENM)
+    /// The card interrupt is enabled on the host. In 4bit mode, the interrupt period between
+    /// blocks (E4MI) is enabled when both the card and the host support it.
+    /// self.sdio_enabled_interrupts is updated
+    pub fn sdio_enable_interrupt(&mut self, function: FunctionSelection) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        let mut interrupt_enable = InterruptEnableRegister {
+            val: self.sdio_enabled_interrupts.val,
+        };
+        interrupt_enable.set_function_interrupt_enabled(function, true);
+        interrupt_enable.set_interrupts_enabled(true);
+        self.sdio_write_interrupt_enable(interrupt_enable)?;
+
+        if self.bus_width == BusWidth::_4BIT && self.mci.is_sdio_interrupt_at_block_gap_capable()? {
+            self.sdio_set_interrupt_at_block_gap(true)?;
+        }
+        self.mci.set_sdio_interrupt_enabled(true)
+    }
+
+    /// Disable the interrupt of a function (IENx)
+    /// The interrupt master (IENM) and the host card interrupt are disabled with the last function
+    /// self.sdio_enabled_interrupts is updated
+    pub fn sdio_disable_interrupt(&mut self, function: FunctionSelection) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        let mut interrupt_enable = InterruptEnableRegister {
+            val: self.sdio_enabled_interrupts.val,
+        };
+        interrupt_enable.set_function_interrupt_enabled(function, false);
+        let last_function = interrupt_enable.val & 0xFE == 0;
+        if last_function {
+            interrupt_enable.set_interrupts_enabled(false);
+            self.mci.set_sdio_interrupt_enabled(false)?;
+        }
+        self.sdio_write_interrupt_enable(interrupt_enable)?;
+
+        if last_function
+            && self.bus_width == BusWidth::_4BIT
+            && self.mci.is_sdio_interrupt_at_block_gap_capable()?
+        {
+            self.sdio_set_interrupt_at_block_gap(false)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the interrupt of a function is enabled
+    pub fn sdio_interrupt_enabled(&self, function: FunctionSelection) -> bool {
+        self.sdio_check_function(function).is_ok()
+            && self
+                .sdio_enabled_interrupts
+                .function_interrupt_enabled(function)
+    }
+
+    /// Read the interrupt pending register (INTx) of the CCCR
+    pub fn sdio_interrupts_pending(&mut self) -> Result<InterruptPendingRegister, MciError> {
+        self.sdio_read_reg::<InterruptPendingRegister>(FunctionSelection::FunctionCia0)
+    }
+
+    /// Call the handler of every enabled function which has an interrupt pending
+    /// To be called when the host reports the card interrupt (mci.is_sdio_interrupt_pending)
+    /// Returns the pending interrupts read from the card
+    pub fn sdio_dispatch_interrupts(&mut self) -> Result<InterruptPendingRegister, MciError> {
+        let pending = self.sdio_interrupts_pending()?;
+        for &function in &FunctionSelection::FUNCTIONS[1..=self.sdio_functions as usize] {
+            if !pending.function_interrupt_pending(function)
+                || !self
+                    .sdio_enabled_interrupts
+                    .function_interrupt_enabled(function)
+            {
+                continue;
+            }
+            if let Some(handler) = self.sdio_interrupt_handlers[function as usize] {
+                handler(self, function);
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Write the interrupt enable register, verified with read after write
+    fn sdio_write_interrupt_enable(
+        &mut self,
+        interrupt_enable: InterruptEnableRegister,
+    ) -> Result<(), MciError> {
+        match self.sdio_write_reg(FunctionSelection::FunctionCia0, interrupt_enable) {
+            Ok(written) => {
+                self.sdio_enabled_interrupts = written;
+                Ok(())
+            }
+            Err(err) => {
+                // Keep following the card when the write did not take
+                if let Ok(current) = self.sdio_read_reg(FunctionSelection::FunctionCia0) {
+                    self.sdio_enabled_interrupts = current;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Enable or disable the interrupt period between blocks of a 4bit multi block transfer
+    /// (E4MI), on the card and on the host. Nothing is done if the card does not support it (S4MI)
+    fn sdio_set_interrupt_at_block_gap(&mut self, enabled: bool) -> Result<(), MciError> {
+        let mut card_capability =
+            self.sdio_read_reg::<CardCapabilityRegister>(FunctionSelection::FunctionCia0)?;
+        if !card_capability.supports_interrupt_between_blocks_of_data_in_4bit_mode() {
+            return Ok(());
+        }
+        if card_capability.enable_interrupt_between_blocks_of_data_in_4bit_sd_mode() != enabled {
+            card_capability.set_enable_interrupt_between_blocks_of_data_in_4bit_sd_mode(enabled);
+            self.sdio_write_reg(FunctionSelection::FunctionCia0, card_capability)?;
+        }
+        self.mci.set_sdio_interrupt_at_block_gap(enabled)
+    }
+}
@@ -0,0 +1,89 @@
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cccr::power_control::PowerControlRegister;
+use crate::registers::sdio::fbr::power_control::PowerControlRegister as FunctionPowerControlRegister;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Whether the card supports master power control (SMPC)
+    pub fn sdio_master_power_control_supported(&mut self) -> Result<bool, MciError> {
+        Ok(self
+            .sdio_read_reg::<PowerControlRegister>(FunctionSelection::FunctionCia0)?
+            .supports_master_power_control())
+    }
+
+    /// Whether master power control is enabled (EMPC)
+    pub fn sdio_master_power_control_enabled(&mut self) -> Result<bool, MciError> {
+        Ok(self
+            .sdio_read_reg::<PowerControlRegister>(FunctionSelection::FunctionCia0)?
+            .enable_master_power_control())
+    }
+
+    /// Enable or disable master power control (EMPC)
+    /// While disabled, every function stays in its default current mode
+    pub fn sdio_set_master_power_control(&mut self, enable: bool) -> Result<(), MciError> {
+        if !self.sdio_master_power_control_supported()? {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        self.sdio_modify_reg(
+            FunctionSelection::FunctionCia0,
+            |r: &mut PowerControlRegister| r.set_enable_master_power_control(enable),
+        )?;
+        Ok(())
+    }
+
+    /// Whether a function supports the high current mode (SPS)
+    pub fn sdio_function_high_current_supported(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<bool, MciError> {
+        Ok(self
+            .sdio_read_reg::<FunctionPowerControlRegister>(function)?
+            .function_supports_power_control())
+    }
+
+    /// Whether a function is in high current mode (EPS)
+    pub fn sdio_function_high_current(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<bool, MciError> {
+        Ok(self
+            .sdio_read_reg::<FunctionPowerControlRegister>(function)?
+            .high_current_mode())
+    }
+
+    /// Select the high or low current mode of a function (EPS)
+    /// The mode is selected while the function is disabled, and only when master power control is
+    /// enabled
+    /// # Arguments
+    /// * `function` Function 1 to 7
+    /// * `high_current` True for high current mode, false for low current mode
+    pub fn sdio_set_function_high_current(
+        &mut self,
+        function: FunctionSelection,
+        high_current: bool,
+    ) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        if self.sdio_function_enabled(function)
+            || !self.sdio_master_power_control_enabled()?
+            || !self.sdio_function_high_current_supported(function)?
+        {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        self.sdio_modify_reg(function, |r: &mut FunctionPowerControlRegister| {
+            r.set_high_current_mode(high_current)
+        })?;
+        Ok(())
+    }
+}
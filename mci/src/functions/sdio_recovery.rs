@@ -0,0 +1,76 @@
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::cccr::io_abort::IoAbortRegister;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Abort the CMD53 transfer in progress on a function (ASx), then reset the host data line
+    /// # Arguments
+    /// * `function` Function 1 to 7
+    pub fn sdio_abort(&mut self, function: FunctionSelection) -> Result<(), MciError> {
+        self.sdio_check_function(function)?;
+        let mut io_abort = IoAbortRegister { val: 0 };
+        io_abort.set_abort_function(function);
+        let result = self
+            .sdio_write_reg(FunctionSelection::FunctionCia0, io_abort)
+            .map(|_| ());
+        // The host data line is reset even if the card did not answer the abort
+        self.mci.reset_data_line()?;
+        result
+    }
+
+    /// Abort the CMD53 transfers of every enabled function
+    pub fn sdio_abort_enabled_functions(&mut self) -> Result<(), MciError> {
+        for &function in &FunctionSelection::FUNCTIONS[1..=self.sdio_functions as usize] {
+            if self.sdio_enabled_functions.function_enabled(function) {
+                self.sdio_abort(function)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reset the IO portion of the card (RES)
+    /// Every function is disabled and the card has to be installed again
+    pub fn sdio_reset(&mut self) -> Result<(), MciError> {
+        let mut io_abort = IoAbortRegister { val: 0 };
+        io_abort.set_card_reset(true);
+        // The card may reset before it answers
+        let _ = self.sdio_write_reg(FunctionSelection::FunctionCia0, io_abort);
+        self.mci.reset_data_line()?;
+        self.sdio_enabled_functions.val = 0;
+        self.sdio_enabled_interrupts.val = 0;
+        Ok(())
+    }
+
+    /// Recover a hung card: abort the transfer of a function, reset the IO portion and install
+    /// the card again
+    /// The memory of a combo card is installed again with it
+    /// Functions come back disabled: drivers have to be attached again
+    /// (SdioDriverRegistry::unbind then bind)
+    /// # Arguments
+    /// * `function` Function whose transfer is aborted. None aborts every enabled function
+    pub fn sdio_recover(&mut self, function: Option<FunctionSelection>) -> Result<(), MciError> {
+        // A function that does not answer the abort is taken care of by the reset
+        let _ = match function {
+            Some(function) => self.sdio_abort(function),
+            None => self.sdio_abort_enabled_functions(),
+        };
+        let combo = self.sdio_is_combo();
+        self.sdio_reset()?;
+        if combo {
+            self.sdio_install_combo()
+        } else {
+            self.sdio_install()
+        }
+    }
+}
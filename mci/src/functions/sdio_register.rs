@@ -0,0 +1,169 @@
+use crate::command_arguments::sdio::cmd52::Direction;
+use crate::functions::sdio::SDIO_FBR_SIZE;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Address of a CCCR or FBR register in the CIA
+    /// # Arguments
+    /// * `function` FunctionCia0 for a CCCR register, function 1 to 7 for a FBR register
+    pub fn sdio_register_address<R: SdioRegister>(
+        &self,
+        function: FunctionSelection,
+    ) -> Result<u32, MciError> {
+        self.sdio_area_address(R::SPACE, function, R::address())
+    }
+
+    /// Address of a byte of the CCCR or of a FBR in the CIA
+    /// # Arguments
+    /// * `space` Register area
+    /// * `function` FunctionCia0 for the CCCR, function 1 to 7 for a FBR
+    /// * `offset` Offset of the byte in the register area
+    pub fn sdio_area_address(
+        &self,
+        space: SdioRegisterSpace,
+        function: FunctionSelection,
+        offset: u8,
+    ) -> Result<u32, MciError> {
+        match space {
+            SdioRegisterSpace::Cccr => {
+                if function != FunctionSelection::FunctionCia0 {
+                    return Err(MciError::Impl(ImplError::InvalidConfiguration));
+                }
+                Ok(offset as u32)
+            }
+            SdioRegisterSpace::Fbr => {
+                self.sdio_check_function(function)?;
+                Ok((function as u32) * SDIO_FBR_SIZE + offset as u32)
+            }
+        }
+    }
+
+    /// Read a CCCR or FBR register
+    /// # Arguments
+    /// * `function` FunctionCia0 for a CCCR register, function 1 to 7 for a FBR register
+    pub fn sdio_read_reg<R: SdioRegister>(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<R, MciError> {
+        let address = self.sdio_register_address::<R>(function)?;
+        Ok(R::from_val(self.sdio_cmd52(
+            Direction::Read,
+            FunctionSelection::FunctionCia0,
+            address,
+            false,
+            0,
+        )?))
+    }
+
+    /// Write a CCCR or FBR register
+    /// The writable bits are verified with read after write
+    /// Returns the register as read back
+    /// # Arguments
+    /// * `function` FunctionCia0 for a CCCR register, function 1 to 7 for a FBR register
+    /// * `register` Value to write
+    pub fn sdio_write_reg<R: SdioRegister>(
+        &mut self,
+        function: FunctionSelection,
+        register: R,
+    ) -> Result<R, MciError> {
+        let address = self.sdio_register_address::<R>(function)?;
+        let read_after_write = R::WRITABLE_BITS != 0;
+        let val = self.sdio_cmd52(
+            Direction::Write,
+            FunctionSelection::FunctionCia0,
+            address,
+            read_after_write,
+            register.val(),
+        )?;
+        if read_after_write && (val ^ register.val()) & R::WRITABLE_BITS != 0 {
+            return Err(MciError::WriteError);
+        }
+        Ok(R::from_val(val))
+    }
+
+    /// Read a CCCR or FBR register, change it and write it back
+    /// Returns the register as read back
+    /// # Arguments
+    /// * `function` FunctionCia0 for a CCCR register, function 1 to 7 for a FBR register
+    /// * `modify` Changes to make to the register
+    pub fn sdio_modify_reg<R, F>(
+        &mut self,
+        function: FunctionSelection,
+        modify: F,
+    ) -> Result<R, MciError>
+    where
+        R: SdioRegister,
+        F: FnOnce(&mut R),
+    {
+        let mut register = self.sdio_read_reg::<R>(function)?;
+        modify(&mut register);
+        self.sdio_write_reg(function, register)
+    }
+
+    /// Read a register of several bytes of the CCCR or of a FBR, like a pointer, LSB first
+    /// # Arguments
+    /// * `space` Register area
+    /// * `function` FunctionCia0 for the CCCR, function 1 to 7 for a FBR
+    /// * `destination` Bytes of the register
+    pub fn sdio_read_reg_bytes<R: RegisterAddress>(
+        &mut self,
+        space: SdioRegisterSpace,
+        function: FunctionSelection,
+        destination: &mut [u8],
+    ) -> Result<(), MciError> {
+        let address = self.sdio_area_address(space, function, R::address())?;
+        for (i, byte) in destination.iter_mut().enumerate() {
+            *byte = self.sdio_cmd52(
+                Direction::Read,
+                FunctionSelection::FunctionCia0,
+                address + i as u32,
+                false,
+                0,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write a register of several bytes of the CCCR or of a FBR, like a pointer, LSB first
+    /// Every byte is verified with read after write
+    /// # Arguments
+    /// * `space` Register area
+    /// * `function` FunctionCia0 for the CCCR, function 1 to 7 for a FBR
+    /// * `source` Bytes of the register
+    pub fn sdio_write_reg_bytes<R: RegisterAddress>(
+        &mut self,
+        space: SdioRegisterSpace,
+        function: FunctionSelection,
+        source: &[u8],
+    ) -> Result<(), MciError> {
+        let address = self.sdio_area_address(space, function, R::address())?;
+        for (i, byte) in source.iter().enumerate() {
+            let val = self.sdio_cmd52(
+                Direction::Write,
+                FunctionSelection::FunctionCia0,
+                address + i as u32,
+                true,
+                *byte,
+            )?;
+            if val != *byte {
+                return Err(MciError::WriteError);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use crate::registers::sdio::cccr::bus_suspend::BusSuspendRegister;
+use crate::registers::sdio::cccr::card_capability::CardCapabilityRegister;
+use crate::registers::sdio::cccr::execution_flags::ExecutionFlagsRegister;
+use crate::registers::sdio::cccr::function_select::{FunctionSelection, FunctionSelectionRegister};
+use crate::registers::sdio::cccr::ready_flags::ReadyFlagsRegister;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Time for the selected function to release the bus after a suspend request, in ms
+pub const SDIO_SUSPEND_TIMEOUT_MS: u32 = 100;
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Whether the card supports suspend/resume (SBS)
+    pub fn sdio_suspend_resume_supported(&mut self) -> Result<bool, MciError> {
+        let card_capability =
+            self.sdio_read_reg::<CardCapabilityRegister>(FunctionSelection::FunctionCia0)?;
+        Ok(card_capability.supports_suspend_resume())
+    }
+
+    /// Function currently selected on the bus (FSx). MemoryInCard is the memory of a combo card
+    pub fn sdio_selected_function(&mut self) -> Result<FunctionSelection, MciError> {
+        let function_selection =
+            self.sdio_read_reg::<FunctionSelectionRegister>(FunctionSelection::FunctionCia0)?;
+        // A reserved selection is not a state the card can be used in
+        function_selection
+            .function_selection()
+            .ok_or(MciError::UnusableCard)
+    }
+
+    /// Suspend the selected function to free the bus for another function or for the memory
+    /// The host stops an ongoing transfer at the block gap, then the bus release is requested (BR)
+    /// and polled until the card completed the suspension
+    /// Returns the suspended function, to be given to sdio_resume
+    pub fn sdio_suspend(&mut self) -> Result<FunctionSelection, MciError> {
+        if !self.sdio_suspend_resume_supported()? {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        let function = self.sdio_selected_function()?;
+        self.mci.stop_at_block_gap()?;
+
+        let mut bus_suspend = BusSuspendRegister { val: 0 };
+        bus_suspend.set_request_release_status(true);
+        self.sdio_write_reg(FunctionSelection::FunctionCia0, bus_suspend)?;
+
+        // The card clears BR once the function is suspended
+        let mut timeout = self.sdio_cmd52_timeout(SDIO_SUSPEND_TIMEOUT_MS);
+        loop {
+            let bus_suspend =
+                self.sdio_read_reg::<BusSuspendRegister>(FunctionSelection::FunctionCia0)?;
+            if !bus_suspend.request_release_status() {
+                return Ok(function);
+            }
+            if timeout.expired(self.monotonic_clock.as_ref()) {
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
+        }
+    }
+
+    /// Select a function, resuming it if it was suspended (FSx)
+    /// When the card reports resume data (DF), the host continues the transfer stopped at the
+    /// block gap
+    /// Returns the resume data flag
+    pub fn sdio_resume(&mut self, function: FunctionSelection) -> Result<bool, MciError> {
+        if function == FunctionSelection::FunctionCia0 {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        if function != FunctionSelection::MemoryInCard {
+            self.sdio_check_function(function)?;
+        }
+        let mut function_selection = FunctionSelectionRegister { val: 0 };
+        function_selection.set_function_selection(function);
+        let function_selection =
+            self.sdio_write_reg(FunctionSelection::FunctionCia0, function_selection)?;
+        let resume_data = function_selection.resume_data_flag();
+        if resume_data {
+            self.mci.continue_at_block_gap()?;
+        }
+        Ok(resume_data)
+    }
+
+    /// Whether a function, or the memory, is executing a command (EXx)
+    pub fn sdio_function_executing(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<bool, MciError> {
+        let execution_flags =
+            self.sdio_read_reg::<ExecutionFlagsRegister>(FunctionSelection::FunctionCia0)?;
+        Ok(execution_flags.function_executing(function))
+    }
+
+    /// Whether a function, or the memory, is ready to transfer data (RFx)
+    pub fn sdio_function_ready_flag(
+        &mut self,
+        function: FunctionSelection,
+    ) -> Result<bool, MciError> {
+        let ready_flags =
+            self.sdio_read_reg::<ReadyFlagsRegister>(FunctionSelection::FunctionCia0)?;
+        Ok(ready_flags.ready_flag(function))
+    }
+}
@@ -1,27 +1,88 @@
 use crate::card_state::CardState;
-use crate::command_arguments::mmc::BusWidth;
+use crate::command_arguments::cmd23::Cmd23;
 use crate::commands::{
-    SDMMC_CMD12_STOP_TRANSMISSION, SDMMC_CMD17_READ_SINGLE_BLOCK, SDMMC_CMD18_READ_MULTIPLE_BLOCK,
+    MMC_CMD23_SET_BLOCK_COUNT, SDMMC_CMD17_READ_SINGLE_BLOCK, SDMMC_CMD18_READ_MULTIPLE_BLOCK,
     SDMMC_CMD24_WRITE_BLOCK, SDMMC_CMD25_WRITE_MULTIPLE_BLOCK, SDMMC_MCI_CMD13_SEND_STATUS,
     SDMMC_MCI_CMD9_SEND_CSD,
 };
+use crate::error::{CommandContext, SdMmcCommandError};
+use crate::functions::sd_mmc_timeout::SD_MMC_COMMAND_POLL_CYCLES;
 use crate::mci::Mci;
 use crate::mci_card::MciCard;
 use crate::registers::csd::CsdRegister;
 use crate::registers::sd::card_status::CardStatusRegister;
-use crate::transfer::TransferTransaction;
+use crate::timeout::MonotonicClock;
+use crate::transfer::{TransferPhase, TransferTransaction};
 use embedded_error::mci::MciError;
 use embedded_error::mci::MciError::UnusableCard;
 use embedded_error::ImplError;
-use embedded_hal::digital::v2::InputPin;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
 
 pub const SD_MMC_BLOCK_SIZE: u32 = 512;
 
-impl<MCI, WP, DETECT> MciCard<MCI, WP, DETECT>
+/// Amount of blocks in a buffer, at least one and at most what a single command transfers
+pub(crate) fn sd_mmc_blocks_amount(len: usize) -> Result<u16, MciError> {
+    let block_size = SD_MMC_BLOCK_SIZE as usize;
+    if len == 0 || len % block_size != 0 || len / block_size > u16::MAX as usize {
+        return Err(MciError::IncorrectDataSize);
+    }
+    Ok((len / block_size) as u16)
+}
+
+/// Read or write command of a block transfer
+/// The blocking, async and polled transfers choose it the same way, see sd_mmc_block_command
+pub(crate) struct BlockCommand {
+    pub cmd: u32,
+    pub arg: u32,
+    /// First block
+    pub start: u32,
+    pub blocks_amount: u16,
+    /// The amount of blocks is set up front with CMD23, the card stops by itself
+    pub block_count_set: bool,
+    /// eMMC reliable write
+    pub reliable_write: bool,
+    pub write: bool,
+}
+
+impl BlockCommand {
+    /// Argument of the CMD23 that sets the amount of blocks
+    pub fn cmd23(&self) -> u32 {
+        let mut cmd23 = Cmd23::default();
+        cmd23
+            .set_block_count(self.blocks_amount)
+            .set_reliable_write(self.reliable_write);
+        cmd23.val
+    }
+
+    /// Error given back for the address, ECC and card internal errors of the R1 response
+    /// A write protect violation is reported as MciError::WriteProtected
+    pub fn data_error(&self) -> MciError {
+        if self.write {
+            MciError::WriteError
+        } else {
+            MciError::ReadError
+        }
+    }
+
+    /// Transaction of the transfer, once the command is started
+    pub fn transaction(&self) -> TransferTransaction {
+        TransferTransaction {
+            amount: self.blocks_amount,
+            remaining: self.blocks_amount,
+            block_count_set: self.block_count_set,
+            phase: TransferPhase::Data,
+            lba: self.start,
+        }
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
 where
     MCI: Mci,
     WP: InputPin,
     DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
 {
     /// CMD9: Card sends its card specific data (CSD)
     /// self.csd is updated
@@ -39,66 +100,166 @@ where
     pub fn sd_mmc_cmd13_get_status_and_wait_for_ready_for_data_flag(
         &mut self,
     ) -> Result<CardStatusRegister, MciError> {
-        let mut status = CardStatusRegister::default();
-        // TODO maybe proper timeout
-        for i in (0..200_000u32).rev() {
-            if i == 0 {
-                return Err(MciError::Impl(ImplError::TimedOut));
+        // The card may still be programming the previous write
+        let mut timeout =
+            self.sd_mmc_timeout(self.sd_mmc_write_timeout_us(), SD_MMC_COMMAND_POLL_CYCLES);
+        loop {
+            // A card removed while it is programming never gets ready
+            self.sd_mmc_check_card_present()?;
+            if let Err(err) = self
+                .mci
+                .send_command(SDMMC_MCI_CMD13_SEND_STATUS.into(), (self.rca as u32) << 16)
+            {
+                return Err(self.sd_mmc_check_removal(err));
             }
-            self.mci
-                .send_command(SDMMC_MCI_CMD13_SEND_STATUS.into(), (self.rca as u32) << 16)?;
-            status = CardStatusRegister {
+            let status = CardStatusRegister {
                 val: self.mci.get_response()?,
             };
             if status.ready_for_data() {
-                break;
+                return Ok(status);
+            }
+            if timeout.expired(self.monotonic_clock.as_ref()) {
+                return Err(MciError::Impl(ImplError::TimedOut));
+            }
+        }
+    }
+
+    /// CMD23: Set the amount of blocks of the next multi block read or write
+    /// # Arguments
+    /// * `blocks_amount` Amount of blocks to be transferred
+    /// * `reliable_write` eMMC only, the next write is a reliable write
+    pub fn sd_mmc_cmd23_set_block_count(
+        &mut self,
+        blocks_amount: u16,
+        reliable_write: bool,
+    ) -> Result<(), MciError> {
+        let mut arg = Cmd23::default();
+        arg.set_block_count(blocks_amount)
+            .set_reliable_write(reliable_write);
+        self.mci
+            .send_command(MMC_CMD23_SET_BLOCK_COUNT.into(), arg.val)
+    }
+
+    /// Choose the command of a block transfer
+    /// A multi block transfer, or a reliable write, is a multiple block command, with the amount
+    /// of blocks set up front (CMD23) when the card supports it
+    /// # Arguments
+    /// * `write` Write, else read
+    /// * `start` First block
+    /// * `blocks_amount` Amount of blocks
+    /// * `reliable_write` eMMC only, the write is a reliable write
+    pub(crate) fn sd_mmc_block_command(
+        &self,
+        write: bool,
+        start: u32,
+        blocks_amount: u16,
+        reliable_write: bool,
+    ) -> BlockCommand {
+        let multiple = blocks_amount > 1 || reliable_write;
+        let cmd: u32 = match (write, multiple) {
+            (false, false) => SDMMC_CMD17_READ_SINGLE_BLOCK.into(),
+            (false, true) => SDMMC_CMD18_READ_MULTIPLE_BLOCK.into(),
+            (true, false) => SDMMC_CMD24_WRITE_BLOCK.into(),
+            (true, true) => SDMMC_CMD25_WRITE_MULTIPLE_BLOCK.into(),
+        };
+        BlockCommand {
+            cmd,
+            arg: self.sd_mmc_block_address(start),
+            start,
+            blocks_amount,
+            block_count_set: multiple && self.set_block_count_supported,
+            reliable_write,
+            write,
+        }
+    }
+
+    /// Start the ADTC of a block command, with the amount of blocks set up front (CMD23) when
+    /// the command asks for it. The host's auto CMD23 is used when it is capable of it.
+    pub(crate) fn sd_mmc_adtc_start_block_command(
+        &mut self,
+        command: &BlockCommand,
+    ) -> Result<(), MciError> {
+        if command.block_count_set {
+            if self.mci.is_auto_cmd23_capable()? {
+                return self.mci.adtc_start_with_auto_cmd23(
+                    command.cmd,
+                    command.arg,
+                    SD_MMC_BLOCK_SIZE as u16,
+                    command.blocks_amount,
+                    true,
+                    command.cmd23(),
+                );
             }
+            self.mci
+                .send_command(MMC_CMD23_SET_BLOCK_COUNT.into(), command.cmd23())?;
         }
-        Ok(status)
+        self.mci.adtc_start(
+            command.cmd,
+            command.arg,
+            SD_MMC_BLOCK_SIZE as u16,
+            command.blocks_amount,
+            true,
+        )
+    }
+
+    /// Check a block command was started: a failed start is recorded with the command, else the
+    /// card status of the response is checked
+    /// # Arguments
+    /// * `command` Command that was started
+    /// * `started` Result of the start
+    pub(crate) fn sd_mmc_check_block_command(
+        &mut self,
+        command: &BlockCommand,
+        started: Result<(), MciError>,
+    ) -> Result<(), SdMmcCommandError> {
+        if let Err(err) = started {
+            let context = CommandContext::new(command.cmd, command.arg, None);
+            return Err(self.sd_mmc_command_error(err, context));
+        }
+        self.sd_mmc_check_r1(command.cmd, command.arg, command.data_error())?;
+        Ok(())
     }
 
     pub fn sd_mmc_deselect_this_device(&mut self) -> Result<(), MciError> {
         self.mci.deselect_device(self.slot)
     }
 
+    /// The error of the host is given back as it is, so a busy host can be told apart from a
+    /// slot it cannot select
     pub fn sd_mmc_select_this_device_on_mci_and_configure_mci(&mut self) -> Result<(), MciError> {
         self.mci
             .select_device(self.slot, self.clock, &self.bus_width, self.high_speed)
-            .map_err(|_| MciError::CouldNotSelectDevice)
     }
 
     /// Select this instance's card slot and initialize the associated driver
+    /// Returns TimedOut while a newly inserted card is still debouncing, try again later
     pub fn sd_mmc_select_slot(&mut self) -> Result<(), MciError> {
         // Check card detection
-        if self.wp.is_high().map_err(|_| MciError::PinLevelReadError)? != self.wp_high_activated {
-            // TODO proper error for pin check
-            if self.state == CardState::Debounce {
-                // TODO Timeout stop?
-            }
-            self.state = CardState::NoCard;
+        self.sd_mmc_card_detect_changed()?;
+        if self.state == CardState::NoCard {
             return Err(MciError::NoCard);
         }
-
-        if self.state == CardState::Debounce {
-            if false {
-                // TODO check if timed out
-                return Err(MciError::Impl(ImplError::TimedOut));
-            }
-            self.state = CardState::Init;
-            // Set 1-bit bus width and low clock for initialization
-            self.clock = 400_000;
-            self.bus_width = BusWidth::_1BIT;
-            self.high_speed = false;
+        if self.state == CardState::Debounce && !self.sd_mmc_debounce_elapsed() {
+            // Still debouncing, try again later
+            return Err(MciError::Impl(ImplError::TimedOut));
         }
         if self.state == CardState::Unusable {
             return Err(UnusableCard);
         }
         self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
-        if self.state == CardState::Init {
-            Ok(())
+        // TODO if it is still ongoing (CardState::Init) should return ongoing
+        Ok(())
+    }
+
+    /// Argument of a block read or write command
+    /// SDSC Card (CCS=0) uses byte unit address,
+    /// SDHC and SDXC Cards (CCS=1) use block unit address (512 Bytes unit).
+    pub(crate) fn sd_mmc_block_address(&self, start: u32) -> u32 {
+        if self.card_type.high_capacity() {
+            start
         } else {
-            Ok(())
-        } // TODO if it is still ongoing should return ongoing
+            start * SD_MMC_BLOCK_SIZE
+        }
     }
 
     pub fn sd_mmc_init_read_blocks(
@@ -109,25 +270,10 @@ where
         self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
         // Wait for data status
         self.sd_mmc_cmd13_get_status_and_wait_for_ready_for_data_flag()?;
-        let cmd: u32 = if blocks_amount > 1 {
-            SDMMC_CMD18_READ_MULTIPLE_BLOCK.into()
-        } else {
-            SDMMC_CMD17_READ_SINGLE_BLOCK.into()
-        };
-
-        // SDSC Card (CCS=0) uses byte unit address,
-        // SDHC and SDXC Cards (CCS=1) use block unit address (512 Bytes unit).
-        let arg = if self.card_type.high_capacity() {
-            start
-        } else {
-            start * SD_MMC_BLOCK_SIZE
-        };
-        self.mci
-            .adtc_start(cmd, arg, SD_MMC_BLOCK_SIZE as u16, blocks_amount, true)?;
-        Ok(TransferTransaction {
-            amount: blocks_amount,
-            remaining: blocks_amount,
-        })
+        let command = self.sd_mmc_block_command(false, start, blocks_amount, false);
+        let started = self.sd_mmc_adtc_start_block_command(&command);
+        self.sd_mmc_check_block_command(&command, started)?;
+        Ok(command.transaction())
     }
 
     pub fn sd_mmc_start_read_blocks(
@@ -155,64 +301,41 @@ where
         } else if transaction.remaining > 0 {
             return Ok(());
         }
+        transaction.phase = TransferPhase::Done;
 
-        // All blocks are transferred then stop read operation
-        if transaction.remaining == 1 {
+        if !transaction.needs_stop(abort) {
             return Ok(());
         }
 
-        // WORKAROUND for no compliance card (Atmel Internal ref. !MMC7 !SD19)
-        // The errors on this cmmand must be ignored and one retry can be necessary in SPI mode
-        // for non-complying card
-        if self
-            .mci
-            .adtc_stop(SDMMC_CMD12_STOP_TRANSMISSION.into(), 0)
-            .is_err()
-        {
-            self.mci
-                .adtc_stop(SDMMC_CMD12_STOP_TRANSMISSION.into(), 0)?; // TODO proper error
-        }
-        Ok(())
+        // Retried for no compliance card (Atmel Internal ref. !MMC7 !SD19)
+        self.sd_mmc_stop_transmission()
     }
 
     pub fn sd_mmc_init_write_blocks(
         &mut self,
         start: u32,
         blocks_amount: u16,
+    ) -> Result<TransferTransaction, MciError> {
+        self.sd_mmc_init_write_blocks_with_reliability(start, blocks_amount, false)
+    }
+
+    /// Start a write. A reliable write (eMMC only) is always a multi block write with the amount
+    /// of blocks set with CMD23
+    pub(crate) fn sd_mmc_init_write_blocks_with_reliability(
+        &mut self,
+        start: u32,
+        blocks_amount: u16,
+        reliable_write: bool,
     ) -> Result<TransferTransaction, MciError> {
         self.sd_mmc_select_this_device_on_mci_and_configure_mci()?;
         if self.write_protected()? {
             return Err(MciError::WriteProtected); // TODO proper write protection error
         }
 
-        let cmd: u32 = if blocks_amount > 1 {
-            SDMMC_CMD25_WRITE_MULTIPLE_BLOCK.into()
-        } else {
-            SDMMC_CMD24_WRITE_BLOCK.into()
-        };
-
-        // SDSC Card (CCS=0) uses byte unit address,
-        // SDHC and SDXC Cards (CCS=1) use block unit address (512 Bytes unit).
-        let arg = if self.card_type.high_capacity() {
-            start
-        } else {
-            start * SD_MMC_BLOCK_SIZE
-        };
-
-        self.mci
-            .adtc_start(cmd, arg, SD_MMC_BLOCK_SIZE as u16, blocks_amount, true)?; // TODO proper error
-
-        let resp = CardStatusRegister {
-            val: self.mci.get_response()?,
-        };
-        if resp.write_protect_violation() {
-            return Err(MciError::WriteProtected);
-        }
-
-        Ok(TransferTransaction {
-            remaining: blocks_amount,
-            amount: blocks_amount,
-        })
+        let command = self.sd_mmc_block_command(true, start, blocks_amount, reliable_write);
+        let started = self.sd_mmc_adtc_start_block_command(&command);
+        self.sd_mmc_check_block_command(&command, started)?;
+        Ok(command.transaction())
     }
 
     pub fn sd_mmc_start_write_blocks(
@@ -240,16 +363,13 @@ where
         } else if transaction.remaining > 0 {
             return Ok(()); // TODO proper return?
         }
+        transaction.phase = TransferPhase::Done;
 
-        // All blocks are transferred then stop write operation
-        if transaction.remaining == 1 {
-            // Single block transfer, then nothing to do
-            return Ok(()); // TODO proper return?
+        if !transaction.needs_stop(abort) {
+            return Ok(());
         }
 
         // Note SPI multi-block writes terminate using a special token, not a STOP_TRANSMISSION request
-        self.mci
-            .adtc_stop(SDMMC_CMD12_STOP_TRANSMISSION.into(), 0)?;
-        Ok(())
+        self.sd_mmc_stop_transmission()
     }
 }
@@ -1,5 +1,7 @@
 #![no_std]
 #![allow(deprecated)]
+#[cfg(feature = "async")]
+pub mod async_mci;
 pub mod card_state;
 pub mod card_type;
 pub mod card_version;
@@ -8,13 +10,21 @@ pub mod command_flags;
 pub mod command_responses;
 pub mod commands;
 pub mod dummy_input_pin;
+pub mod dummy_output_pin;
 pub mod error;
 pub mod functions;
 pub mod mci;
+pub mod mci_bus;
 pub mod mci_card;
 pub mod mode_index;
+pub mod recovery;
 pub mod registers;
 pub mod sd;
 #[cfg(feature = "sdio")]
+pub mod sdio_drivers;
+#[cfg(feature = "sdio")]
 pub mod sdio_state;
+#[cfg(all(test, feature = "sdio"))]
+mod test_mci;
+pub mod timeout;
 pub mod transfer;
@@ -1,7 +1,8 @@
 use crate::command_arguments::mmc::BusWidth;
 use embedded_error::mci::MciError;
+use embedded_error::ImplError;
 
-// TODO keep + get current selected slot
+/// Host controller. Several slots of one host are shared with crate::mci_bus::MciBus
 pub trait Mci {
     /// Initialize MCI low level driver.
     fn init(&mut self) -> Result<(), MciError>;
@@ -47,7 +48,7 @@ pub trait Mci {
     /// * `block_size`: 16bit block size
     /// * `block_amount`: Amount of blocks to transfer
     /// * `access_in_blocks`: If true - read_blocks/write_blocks must be used after this command
-    ///                 Otherwise read_word/write_word must be used
+    ///   Otherwise read_word/write_word must be used
     fn adtc_start(
         &mut self,
         command: u32,
@@ -57,6 +58,81 @@ pub trait Mci {
         access_in_blocks: bool,
     ) -> Result<(), MciError>;
 
+    /// Whether the host can send a CMD23 (SET_BLOCK_COUNT) by itself ahead of a multi block
+    /// ADTC command (auto CMD23)
+    fn is_auto_cmd23_capable(&mut self) -> Result<bool, MciError> {
+        Ok(false)
+    }
+
+    /// ADTC command start, preceded by an auto CMD23 sent by the host
+    /// Only used when is_auto_cmd23_capable is true
+    ///
+    /// # Arguments
+    /// * `command`: 32bit command
+    /// * `argument`: Argument of the command
+    /// * `block_size`: 16bit block size
+    /// * `block_amount`: Amount of blocks to transfer
+    /// * `access_in_blocks`: If true - read_blocks/write_blocks must be used after this command
+    ///   Otherwise read_word/write_word must be used
+    /// * `cmd23_argument`: Argument of the CMD23 (block count and reliable write flag)
+    fn adtc_start_with_auto_cmd23(
+        &mut self,
+        _command: u32,
+        _argument: u32,
+        _block_size: u16,
+        _block_amount: u16,
+        _access_in_blocks: bool,
+        _cmd23_argument: u32,
+    ) -> Result<(), MciError> {
+        Err(MciError::Impl(ImplError::InvalidConfiguration))
+    }
+
+    /// Enable or disable the SDIO card interrupt, signalled by the card on DAT1
+    fn set_sdio_interrupt_enabled(&mut self, _enabled: bool) -> Result<(), MciError> {
+        Err(MciError::Impl(ImplError::InvalidConfiguration))
+    }
+
+    /// Whether the card signals an SDIO interrupt on DAT1
+    /// The card keeps DAT1 asserted until the interrupt source of the function is cleared
+    fn is_sdio_interrupt_pending(&mut self) -> Result<bool, MciError> {
+        Ok(false)
+    }
+
+    /// Whether the host detects SDIO interrupts in the interrupt period between blocks of a 4bit
+    /// multi block transfer
+    fn is_sdio_interrupt_at_block_gap_capable(&mut self) -> Result<bool, MciError> {
+        Ok(false)
+    }
+
+    /// Enable or disable the detection of SDIO interrupts between blocks of a 4bit multi block
+    /// transfer
+    /// Only used when is_sdio_interrupt_at_block_gap_capable is true
+    fn set_sdio_interrupt_at_block_gap(&mut self, _enabled: bool) -> Result<(), MciError> {
+        Err(MciError::Impl(ImplError::InvalidConfiguration))
+    }
+
+    /// Stop the ongoing data transfer at the next block gap, before an SDIO function is suspended
+    /// Nothing is done when no data transfer is ongoing
+    fn stop_at_block_gap(&mut self) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    /// Continue the data transfer stopped at a block gap, after an SDIO function is resumed
+    fn continue_at_block_gap(&mut self) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    /// Reset the data line of the host after an aborted data transfer
+    fn reset_data_line(&mut self) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    /// Reset the host after the card was removed
+    /// The ongoing command and data transfer are dropped, the host stays initialized
+    fn reset_host(&mut self) -> Result<(), MciError> {
+        self.reset_data_line()
+    }
+
     /// ADTC command stop
     /// Send a command to stop an ADTC
     /// # Arguments
@@ -86,6 +162,28 @@ pub trait Mci {
     ///  * `number_of_blocks` - Number of blocks to write
     fn write_blocks(&mut self, data: &[u8], number_of_blocks: u16) -> Result<bool, MciError>;
 
+    /// Whether the next block of the ongoing read is in the host buffer, so read_blocks of one
+    /// block does not wait on the card
+    /// A host that cannot tell always reports ready, and read_blocks waits
+    fn is_read_buffer_ready(&mut self) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
+    /// Whether the host buffer takes the next block of the ongoing write, so write_blocks of one
+    /// block does not wait on the card
+    /// A host that cannot tell always reports ready, and write_blocks waits
+    fn is_write_buffer_ready(&mut self) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
+    /// Whether the data transfer is complete once all its data is moved, which for a write
+    /// includes the card busy. wait_until_read_finished or wait_until_write_finished then does
+    /// not wait
+    /// A host that cannot tell always reports complete, and the wait functions wait
+    fn is_transfer_complete(&mut self) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
     /// Wait until the end of reading the blocks
     fn wait_until_read_finished(&self) -> Result<(), MciError>;
 
@@ -0,0 +1,390 @@
+use crate::command_arguments::mci_command::MciCommand;
+use crate::command_arguments::mmc::BusWidth;
+use crate::commands::{
+    MMC_CMD23_SET_BLOCK_COUNT, SDMMC_CMD18_READ_MULTIPLE_BLOCK, SDMMC_CMD25_WRITE_MULTIPLE_BLOCK,
+};
+use crate::mci::Mci;
+use core::cell::{Cell, RefCell, RefMut};
+use embedded_error::mci::MciError;
+
+/// Bus settings of a slot, restored when the slot is selected again
+#[derive(Copy, Clone)]
+struct SlotConfig {
+    clock: u32,
+    bus_width: BusWidth,
+    high_speed: bool,
+}
+
+/// Data transfer started on a slot and not ended yet
+#[derive(Copy, Clone)]
+struct OpenTransfer {
+    slot: u8,
+    /// The card keeps transferring until CMD12, the end of the data does not end the transfer
+    stop_needed: bool,
+}
+
+/// Host controller shared by the cards of several slots
+/// Each card gets an MciSlot of the bus as its Mci. The slot of the card is selected again, with its
+/// clock and bus width, when it uses the host after another slot did
+/// A data transfer holds the host from adtc_start until wait_until_read_finished,
+/// wait_until_write_finished, adtc_stop or a reset of its slot. An open ended multi block transfer
+/// holds it until adtc_stop. Meanwhile the other slots get CommandInhibited
+pub struct MciBus<MCI>
+where
+    MCI: Mci,
+{
+    host: RefCell<MCI>,
+    initialized: Cell<bool>,
+    selected: Cell<Option<u8>>,
+    transfer: Cell<Option<OpenTransfer>>,
+    /// Slot that sent CMD23 as its last command, its next multi block transfer ends by itself
+    /// Any other command, from any slot, clears it
+    block_count_set: Cell<Option<u8>>,
+}
+
+impl<MCI> MciBus<MCI>
+where
+    MCI: Mci,
+{
+    /// # Arguments
+    /// * `host` Host controller, initialized or not
+    pub fn new(host: MCI) -> Self {
+        MciBus {
+            host: RefCell::new(host),
+            initialized: Cell::new(false),
+            selected: Cell::new(None),
+            transfer: Cell::new(None),
+            block_count_set: Cell::new(None),
+        }
+    }
+
+    /// Handle of a slot, to be given to the MciCard of that slot
+    /// The slot of the handle is used, not the one the card passes to the Mci
+    /// # Arguments
+    /// * `slot` Slot number on the host
+    pub fn slot(&self, slot: u8) -> MciSlot<'_, MCI> {
+        MciSlot {
+            bus: self,
+            slot,
+            config: Cell::new(None),
+        }
+    }
+
+    /// Slot currently selected on the host, if any
+    pub fn selected_slot(&self) -> Option<u8> {
+        self.selected.get()
+    }
+
+    /// Slot whose data transfer holds the host, if any
+    pub fn transfer_slot(&self) -> Option<u8> {
+        self.transfer.get().map(|transfer| transfer.slot)
+    }
+
+    /// Give the host back, once all slot handles are dropped
+    pub fn release(self) -> MCI {
+        self.host.into_inner()
+    }
+
+    /// Borrow the host for a slot
+    /// Fails with CommandInhibited when the host is already in use, or another slot has a data
+    /// transfer open
+    fn host(&self, slot: u8) -> Result<RefMut<'_, MCI>, MciError> {
+        match self.transfer.get() {
+            Some(transfer) if transfer.slot != slot => Err(MciError::CommandInhibited),
+            _ => self
+                .host
+                .try_borrow_mut()
+                .map_err(|_| MciError::CommandInhibited),
+        }
+    }
+
+    /// Run on the host for a slot, selecting the slot first if another one is selected
+    /// Fails with CommandInhibited when the host is in use, and with CouldNotSelectDevice when the
+    /// slot never selected its device, so it has no settings to restore
+    fn with_host<T, F>(&self, slot: &MciSlot<'_, MCI>, f: F) -> Result<T, MciError>
+    where
+        F: FnOnce(&mut MCI) -> Result<T, MciError>,
+    {
+        let mut host = self.host(slot.slot)?;
+        if self.selected.get() != Some(slot.slot) {
+            let config = slot.config.get().ok_or(MciError::CouldNotSelectDevice)?;
+            host.select_device(
+                slot.slot,
+                config.clock,
+                &config.bus_width,
+                config.high_speed,
+            )?;
+            self.selected.set(Some(slot.slot));
+        }
+        f(&mut host)
+    }
+
+    /// Whether a data command runs until CMD12
+    fn open_ended(command: u32) -> bool {
+        let index = MciCommand::from(command).index();
+        index == MciCommand::from(u32::from(SDMMC_CMD18_READ_MULTIPLE_BLOCK)).index()
+            || index == MciCommand::from(u32::from(SDMMC_CMD25_WRITE_MULTIPLE_BLOCK)).index()
+    }
+}
+
+/// Slot of an MciBus, the Mci of the card in that slot
+pub struct MciSlot<'a, MCI>
+where
+    MCI: Mci,
+{
+    bus: &'a MciBus<MCI>,
+    slot: u8,
+    config: Cell<Option<SlotConfig>>,
+}
+
+impl<'a, MCI> MciSlot<'a, MCI>
+where
+    MCI: Mci,
+{
+    /// Slot number on the host
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
+    fn with_host<T, F>(&self, f: F) -> Result<T, MciError>
+    where
+        F: FnOnce(&mut MCI) -> Result<T, MciError>,
+    {
+        self.bus.with_host(self, f)
+    }
+
+    /// Start a data transfer, which holds the host until it is ended
+    /// # Arguments
+    /// * `stop_needed` The transfer only ends with adtc_stop
+    fn start_transfer<F>(&self, stop_needed: bool, f: F) -> Result<(), MciError>
+    where
+        F: FnOnce(&mut MCI) -> Result<(), MciError>,
+    {
+        self.with_host(f)?;
+        self.bus.transfer.set(Some(OpenTransfer {
+            slot: self.slot,
+            stop_needed,
+        }));
+        Ok(())
+    }
+
+    /// Run on the host and end the data transfer of this slot, even if the call fails
+    /// # Arguments
+    /// * `stop` The call stops the transfer, which also ends an open ended transfer
+    fn end_transfer<T, F>(&self, stop: bool, f: F) -> Result<T, MciError>
+    where
+        F: FnOnce(&mut MCI) -> Result<T, MciError>,
+    {
+        let result = self.with_host(f);
+        if let Some(transfer) = self.bus.transfer.get() {
+            if transfer.slot == self.slot && (stop || !transfer.stop_needed) {
+                self.bus.transfer.set(None);
+            }
+        }
+        result
+    }
+}
+
+impl<'a, MCI> Mci for MciSlot<'a, MCI>
+where
+    MCI: Mci,
+{
+    /// Initializes the host, once for all slots
+    fn init(&mut self) -> Result<(), MciError> {
+        if self.bus.initialized.get() {
+            return Ok(());
+        }
+        // No slot is selected before the host is initialized
+        self.bus.host(self.slot)?.init()?;
+        self.bus.initialized.set(true);
+        Ok(())
+    }
+
+    fn send_command(&mut self, cmd: u32, arg: u32) -> Result<(), MciError> {
+        self.bus.block_count_set.set(None);
+        self.with_host(|host| host.send_command(cmd, arg))?;
+        if MciCommand::from(cmd).index()
+            == MciCommand::from(u32::from(MMC_CMD23_SET_BLOCK_COUNT)).index()
+        {
+            self.bus.block_count_set.set(Some(self.slot));
+        }
+        Ok(())
+    }
+
+    /// Deselects this slot, the host stays initialized for the other slots
+    fn deinit(&mut self) -> Result<(), MciError> {
+        self.deselect_device(self.slot)
+    }
+
+    fn select_device(
+        &mut self,
+        _slot: u8,
+        clock: u32,
+        bus_width: &BusWidth,
+        high_speed: bool,
+    ) -> Result<(), MciError> {
+        let mut host = self.bus.host(self.slot)?;
+        self.config.set(Some(SlotConfig {
+            clock,
+            bus_width: *bus_width,
+            high_speed,
+        }));
+        // Selected again on the next use if this fails
+        self.bus.selected.set(None);
+        host.select_device(self.slot, clock, bus_width, high_speed)?;
+        self.bus.selected.set(Some(self.slot));
+        Ok(())
+    }
+
+    fn deselect_device(&mut self, _slot: u8) -> Result<(), MciError> {
+        let mut host = self.bus.host(self.slot)?;
+        if self.bus.selected.get() == Some(self.slot) {
+            self.bus.selected.set(None);
+        }
+        host.deselect_device(self.slot)
+    }
+
+    fn get_bus_width(&mut self, _slot: u8) -> Result<BusWidth, MciError> {
+        let slot = self.slot;
+        self.with_host(|host| host.get_bus_width(slot))
+    }
+
+    fn is_high_speed_capable(&mut self) -> Result<bool, MciError> {
+        self.with_host(|host| host.is_high_speed_capable())
+    }
+
+    fn send_clock(&mut self) -> Result<(), MciError> {
+        self.with_host(|host| host.send_clock())
+    }
+
+    fn get_response(&mut self) -> Result<u32, MciError> {
+        self.with_host(|host| host.get_response())
+    }
+
+    fn get_response128(&mut self) -> Result<[u32; 4], MciError> {
+        self.with_host(|host| host.get_response128())
+    }
+
+    fn adtc_start(
+        &mut self,
+        command: u32,
+        argument: u32,
+        block_size: u16,
+        block_amount: u16,
+        access_in_blocks: bool,
+    ) -> Result<(), MciError> {
+        let block_count_set = self.bus.block_count_set.take() == Some(self.slot);
+        let stop_needed = !block_count_set && MciBus::<MCI>::open_ended(command);
+        self.start_transfer(stop_needed, |host| {
+            host.adtc_start(
+                command,
+                argument,
+                block_size,
+                block_amount,
+                access_in_blocks,
+            )
+        })
+    }
+
+    fn is_auto_cmd23_capable(&mut self) -> Result<bool, MciError> {
+        self.with_host(|host| host.is_auto_cmd23_capable())
+    }
+
+    fn adtc_start_with_auto_cmd23(
+        &mut self,
+        command: u32,
+        argument: u32,
+        block_size: u16,
+        block_amount: u16,
+        access_in_blocks: bool,
+        cmd23_argument: u32,
+    ) -> Result<(), MciError> {
+        self.bus.block_count_set.set(None);
+        self.start_transfer(false, |host| {
+            host.adtc_start_with_auto_cmd23(
+                command,
+                argument,
+                block_size,
+                block_amount,
+                access_in_blocks,
+                cmd23_argument,
+            )
+        })
+    }
+
+    fn set_sdio_interrupt_enabled(&mut self, enabled: bool) -> Result<(), MciError> {
+        self.with_host(|host| host.set_sdio_interrupt_enabled(enabled))
+    }
+
+    fn is_sdio_interrupt_pending(&mut self) -> Result<bool, MciError> {
+        self.with_host(|host| host.is_sdio_interrupt_pending())
+    }
+
+    fn is_sdio_interrupt_at_block_gap_capable(&mut self) -> Result<bool, MciError> {
+        self.with_host(|host| host.is_sdio_interrupt_at_block_gap_capable())
+    }
+
+    fn set_sdio_interrupt_at_block_gap(&mut self, enabled: bool) -> Result<(), MciError> {
+        self.with_host(|host| host.set_sdio_interrupt_at_block_gap(enabled))
+    }
+
+    fn stop_at_block_gap(&mut self) -> Result<(), MciError> {
+        self.with_host(|host| host.stop_at_block_gap())
+    }
+
+    fn continue_at_block_gap(&mut self) -> Result<(), MciError> {
+        self.with_host(|host| host.continue_at_block_gap())
+    }
+
+    fn reset_data_line(&mut self) -> Result<(), MciError> {
+        self.end_transfer(true, |host| host.reset_data_line())
+    }
+
+    fn reset_host(&mut self) -> Result<(), MciError> {
+        self.end_transfer(true, |host| host.reset_host())
+    }
+
+    fn adtc_stop(&self, command: u32, argument: u32) -> Result<(), MciError> {
+        self.end_transfer(true, |host| host.adtc_stop(command, argument))
+    }
+
+    fn read_word(&mut self) -> Result<(u32, u8), MciError> {
+        self.with_host(|host| host.read_word())
+    }
+
+    fn write_word(&mut self, val: u32) -> Result<bool, MciError> {
+        self.with_host(|host| host.write_word(val))
+    }
+
+    fn read_blocks(
+        &mut self,
+        destination: &mut [u8],
+        number_of_blocks: u16,
+    ) -> Result<bool, MciError> {
+        self.with_host(|host| host.read_blocks(destination, number_of_blocks))
+    }
+
+    fn write_blocks(&mut self, data: &[u8], number_of_blocks: u16) -> Result<bool, MciError> {
+        self.with_host(|host| host.write_blocks(data, number_of_blocks))
+    }
+
+    fn is_read_buffer_ready(&mut self) -> Result<bool, MciError> {
+        self.with_host(|host| host.is_read_buffer_ready())
+    }
+
+    fn is_write_buffer_ready(&mut self) -> Result<bool, MciError> {
+        self.with_host(|host| host.is_write_buffer_ready())
+    }
+
+    fn is_transfer_complete(&mut self) -> Result<bool, MciError> {
+        self.with_host(|host| host.is_transfer_complete())
+    }
+
+    fn wait_until_read_finished(&self) -> Result<(), MciError> {
+        self.end_transfer(false, |host| host.wait_until_read_finished())
+    }
+
+    fn wait_until_write_finished(&self) -> Result<(), MciError> {
+        self.end_transfer(false, |host| host.wait_until_write_finished())
+    }
+}
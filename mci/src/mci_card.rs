@@ -2,11 +2,26 @@ use crate::card_state::CardState;
 use crate::card_type::CardType;
 use crate::card_version::CardVersion;
 use crate::command_arguments::mmc::BusWidth;
+use crate::dummy_output_pin::DummyOutputPin;
+use crate::error::CommandContext;
+use crate::functions::sd_mmc_hot_plug::{CardEventHandler, CardInstall};
+use crate::functions::sd_mmc_removal::CardRemovalDetection;
+#[cfg(feature = "sdio")]
+use crate::functions::sdio_interrupt::SdioInterruptHandlers;
 use crate::mci::Mci;
+use crate::recovery::{RecoveryPolicy, RecoveryState};
 use crate::registers::csd::CsdRegister;
 use crate::registers::ocr::OcrRegister;
+#[cfg(feature = "sdio")]
+use crate::registers::sdio::cccr::interrupt_enable::InterruptEnableRegister;
+#[cfg(feature = "sdio")]
+use crate::registers::sdio::cccr::io_enable::IoEnableRegister;
+use crate::timeout::{
+    MonotonicClock, NoClock, SD_MMC_DEBOUNCE_POLLS, SD_MMC_POWER_RAMP_DOWN_US,
+    SD_MMC_POWER_RAMP_UP_US,
+};
 use embedded_error::mci::MciError;
-use embedded_hal::digital::v2::InputPin;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
 
 // SD/MMC transfer rate unit codes (10K) list
 pub const SD_MMC_TRANS_UNITS: [u32; 7] = [10, 100, 1_000, 10_000, 0, 0, 0];
@@ -19,11 +34,13 @@ pub const MMC_TRANS_MULTIPLIERS: [u32; 16] = [
     0, 10, 12, 13, 15, 20, 26, 30, 35, 40, 45, 52, 55, 60, 70, 80,
 ];
 
-pub struct MciCard<MCI, WP, DETECT>
+pub struct MciCard<MCI, WP, DETECT, POWER = DummyOutputPin, CLOCK = NoClock>
 where
     MCI: Mci,
-    WP: InputPin,     // Write protect pin
-    DETECT: InputPin, // Card detect pin
+    WP: InputPin,          // Write protect pin
+    DETECT: InputPin,      // Card detect pin
+    POWER: OutputPin,      // Card power switch pin
+    CLOCK: MonotonicClock, // Time source of the timeouts
 {
     /// Hardware interface
     pub mci: MCI,
@@ -45,6 +62,32 @@ where
     pub csd: CsdRegister,
     /// High speed card
     pub high_speed: bool,
+    /// Card supports pre-defined multi block transfers (CMD23 SET_BLOCK_COUNT)
+    pub set_block_count_supported: bool,
+    /// eMMC supports reliable writes
+    pub reliable_write_supported: bool,
+    /// eMMC reliable write sector count (EXT_CSD REL_WR_SEC_C)
+    pub reliable_write_sector_count: u8,
+    /// eMMC reliable writes of any size and alignment (EXT_CSD WR_REL_PARAM EN_REL_WR)
+    pub enhanced_reliable_write: bool,
+    /// Amount of SDIO functions, function 0 excluded
+    #[cfg(feature = "sdio")]
+    pub sdio_functions: u8,
+    /// Block size set for each SDIO function, function 0 included
+    #[cfg(feature = "sdio")]
+    pub sdio_block_sizes: [u16; 8],
+    /// SDIO card supports CMD53 in block mode (SMB)
+    #[cfg(feature = "sdio")]
+    pub sdio_multi_block_supported: bool,
+    /// SDIO functions that are enabled, as last written to the CCCR
+    #[cfg(feature = "sdio")]
+    pub sdio_enabled_functions: IoEnableRegister,
+    /// SDIO interrupts that are enabled, as last written to the CCCR
+    #[cfg(feature = "sdio")]
+    pub sdio_enabled_interrupts: InterruptEnableRegister,
+    /// Interrupt handler of each SDIO function, function 0 included
+    #[cfg(feature = "sdio")]
+    pub sdio_interrupt_handlers: SdioInterruptHandlers<MCI, WP, DETECT, POWER, CLOCK>,
     /// This card's slot number
     pub slot: u8,
     /// Write protect pin
@@ -55,6 +98,39 @@ where
     pub detect: DETECT,
     /// Whether a pulled high pin is logic true that a card is detected
     pub detect_high_activated: bool,
+    /// Time source of the timeouts. Without one the timeouts are amounts of polls at self.clock,
+    /// and the card detect pin is debounced over self.debounce_polls polls
+    pub monotonic_clock: Option<CLOCK>,
+    /// When the card detect pin started to debounce, from self.monotonic_clock
+    pub debounce_start_us: Option<u32>,
+    /// Polls of the card detect pin a card is debounced over, without self.monotonic_clock
+    /// Defaults to SD_MMC_DEBOUNCE_POLLS, to be set from the poll period of the application
+    pub debounce_polls: u16,
+    /// Polls since the card detect pin started to debounce, without self.monotonic_clock
+    pub debounce_polls_counted: u16,
+    /// How commands are retried and failed transfers recovered
+    pub recovery_policy: RecoveryPolicy,
+    /// Failures seen since the last successful transfer
+    pub recovery_state: RecoveryState,
+    /// Last command that failed, with the response of the card
+    pub last_command_error: Option<CommandContext>,
+    /// Called on each insertion, removal, install and write protect change
+    pub card_event_handler: Option<CardEventHandler<MCI, WP, DETECT, POWER, CLOCK>>,
+    /// Install run by sd_mmc_poll_card_detect once an inserted card is debounced
+    pub card_install: Option<CardInstall<MCI, WP, DETECT, POWER, CLOCK>>,
+    /// Write protect pin as last read while the card is present
+    pub last_write_protected: Option<bool>,
+    /// How a card removed during an operation is noticed. Defaults to Disabled, a board with a
+    /// real card detect pin opts in with DetectPin, one without with CommandTimeout
+    pub removal_detection: CardRemovalDetection,
+    /// Card power switch pin, None when the card is always powered
+    pub power: Option<POWER>,
+    /// Whether a pulled high pin powers the card
+    pub power_high_activated: bool,
+    /// Time the supply needs to ramp up after power on, in us. Needs self.monotonic_clock
+    pub power_ramp_up_us: u32,
+    /// Time the supply needs to drop after power off, in us. Needs self.monotonic_clock
+    pub power_ramp_down_us: u32,
 }
 
 pub fn ocr_voltage_support() -> OcrRegister {
@@ -68,13 +144,17 @@ pub fn ocr_voltage_support() -> OcrRegister {
     ocr
 }
 
-impl<MCI, WP, DETECT> MciCard<MCI, WP, DETECT>
+impl<MCI, WP, DETECT> MciCard<MCI, WP, DETECT, DummyOutputPin, NoClock>
 where
     MCI: Mci,
     WP: InputPin,
     DETECT: InputPin,
 {
     /// Create a new SD MMC instance
+    /// Without a monotonic clock the timeouts are amounts of polls, see new_with_monotonic_clock
+    /// A card removed during an operation is not noticed until removal_detection is set: callers
+    /// opt in with CardRemovalDetection::DetectPin when detect_pin is a real card detect pin, or
+    /// with CommandTimeout when the slot has none
     pub fn new(
         mci: MCI,
         write_protect_pin: WP,
@@ -82,6 +162,102 @@ where
         detect_pin: DETECT,
         detect_high_activated: bool,
         slot: u8,
+    ) -> Self {
+        Self::create(
+            mci,
+            write_protect_pin,
+            wp_high_activated,
+            detect_pin,
+            detect_high_activated,
+            slot,
+            None,
+            true,
+            None,
+        )
+    }
+}
+
+impl<MCI, WP, DETECT, CLOCK> MciCard<MCI, WP, DETECT, DummyOutputPin, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Create a new SD MMC instance whose timeouts and card detect debounce are timed on a
+    /// monotonic clock
+    /// Removals are detected once opted in, as with new
+    pub fn new_with_monotonic_clock(
+        mci: MCI,
+        write_protect_pin: WP,
+        wp_high_activated: bool,
+        detect_pin: DETECT,
+        detect_high_activated: bool,
+        slot: u8,
+        monotonic_clock: CLOCK,
+    ) -> Self {
+        Self::create(
+            mci,
+            write_protect_pin,
+            wp_high_activated,
+            detect_pin,
+            detect_high_activated,
+            slot,
+            None,
+            true,
+            Some(monotonic_clock),
+        )
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> MciCard<MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Create a new SD MMC instance with a card power switch
+    /// The card is powered on with sd_mmc_power_on, which waits for the supply to ramp on the
+    /// monotonic clock
+    /// Removals are detected once opted in, as with new
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_power_pin(
+        mci: MCI,
+        write_protect_pin: WP,
+        wp_high_activated: bool,
+        detect_pin: DETECT,
+        detect_high_activated: bool,
+        slot: u8,
+        power_pin: POWER,
+        power_high_activated: bool,
+        monotonic_clock: CLOCK,
+    ) -> Self {
+        Self::create(
+            mci,
+            write_protect_pin,
+            wp_high_activated,
+            detect_pin,
+            detect_high_activated,
+            slot,
+            Some(power_pin),
+            power_high_activated,
+            Some(monotonic_clock),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create(
+        mci: MCI,
+        write_protect_pin: WP,
+        wp_high_activated: bool,
+        detect_pin: DETECT,
+        detect_high_activated: bool,
+        slot: u8,
+        power: Option<POWER>,
+        power_high_activated: bool,
+        monotonic_clock: Option<CLOCK>,
     ) -> Self {
         MciCard {
             mci,
@@ -94,11 +270,42 @@ where
             bus_width: BusWidth::_1BIT,
             csd: Default::default(),
             high_speed: false,
+            set_block_count_supported: false,
+            reliable_write_supported: false,
+            reliable_write_sector_count: 0,
+            enhanced_reliable_write: false,
+            #[cfg(feature = "sdio")]
+            sdio_functions: 0,
+            #[cfg(feature = "sdio")]
+            sdio_block_sizes: [0; 8],
+            #[cfg(feature = "sdio")]
+            sdio_multi_block_supported: false,
+            #[cfg(feature = "sdio")]
+            sdio_enabled_functions: IoEnableRegister { val: 0 },
+            #[cfg(feature = "sdio")]
+            sdio_enabled_interrupts: InterruptEnableRegister { val: 0 },
+            #[cfg(feature = "sdio")]
+            sdio_interrupt_handlers: [None; 8],
             slot,
             wp: write_protect_pin,
             wp_high_activated,
             detect: detect_pin,
             detect_high_activated,
+            monotonic_clock,
+            debounce_start_us: None,
+            debounce_polls: SD_MMC_DEBOUNCE_POLLS,
+            debounce_polls_counted: 0,
+            recovery_policy: RecoveryPolicy::default(),
+            recovery_state: RecoveryState::default(),
+            last_command_error: None,
+            card_event_handler: None,
+            card_install: None,
+            last_write_protected: None,
+            removal_detection: CardRemovalDetection::Disabled,
+            power,
+            power_high_activated,
+            power_ramp_up_us: SD_MMC_POWER_RAMP_UP_US,
+            power_ramp_down_us: SD_MMC_POWER_RAMP_DOWN_US,
         }
     }
 
@@ -106,4 +313,13 @@ where
         let level = self.wp.is_high().map_err(|_| MciError::PinLevelReadError)?; //TODO proper error for pin fault
         Ok(level == self.wp_high_activated)
     }
+
+    /// Whether the card detect pin shows a card
+    pub fn card_detected(&self) -> Result<bool, MciError> {
+        let level = self
+            .detect
+            .is_high()
+            .map_err(|_| MciError::PinLevelReadError)?;
+        Ok(level == self.detect_high_activated)
+    }
 }
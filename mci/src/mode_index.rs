@@ -1,6 +1,7 @@
 use core::hint::unreachable_unchecked;
 
 pub enum ModeIndex {
+    PowerOffNotification = 0x22,
     EraseGroupDef = 0xAF,
     BootBusWidth = 0xB1,
     BootConfig = 0xB3,
@@ -15,6 +16,7 @@ pub enum ModeIndex {
 impl From<u32> for ModeIndex {
     fn from(val: u32) -> Self {
         match val {
+            0x22 => ModeIndex::PowerOffNotification,
             0xAF => ModeIndex::EraseGroupDef,
             0xB1 => ModeIndex::BootBusWidth,
             0xB3 => ModeIndex::BootConfig,
@@ -0,0 +1,232 @@
+use embedded_error::mci::{CommandOrDataError, MciError};
+use embedded_error::ImplError;
+
+/// What to do after a block transfer failed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RecoveryAction {
+    /// Try the transfer again
+    Retry,
+    /// Lower the bus clock, then try the transfer again
+    StepClockDown,
+    /// Install the card again, then try the transfer again
+    Reinitialize,
+    /// Give the error back, the card stays usable
+    Fail,
+    /// Give the error back and mark the card Unusable
+    GiveUp,
+}
+
+/// Failures seen since the last successful transfer
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RecoveryState {
+    /// Failed transfers in a row
+    pub consecutive_failures: u8,
+    /// Failed transfers in a row that were CRC errors
+    pub consecutive_crc_errors: u8,
+    /// Re-initializations since the last successful transfer
+    pub reinits: u8,
+}
+
+impl RecoveryState {
+    /// Account for a failed transfer
+    pub fn failed(&mut self, error: &MciError) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.consecutive_crc_errors = if is_crc_error(error) {
+            self.consecutive_crc_errors.saturating_add(1)
+        } else {
+            0
+        };
+    }
+
+    /// Forget the failures after a successful transfer
+    pub fn succeeded(&mut self) {
+        *self = RecoveryState::default();
+    }
+}
+
+/// Decision replacing the one of RecoveryPolicy::decide
+pub type RecoveryDecision = fn(&RecoveryPolicy, &RecoveryState, &MciError) -> RecoveryAction;
+
+/// Retry and recovery policy of a card
+#[derive(Copy, Clone)]
+pub struct RecoveryPolicy {
+    /// Attempts of a command on CRC and timeout errors, the first one included
+    pub command_attempts: u8,
+    /// CRC errors in a row before the clock is stepped down
+    pub crc_errors_before_clock_step_down: u8,
+    /// Failed transfers in a row before the card is installed again
+    pub failures_before_reinit: u8,
+    /// Re-initializations before the card is marked Unusable
+    pub max_reinits: u8,
+    /// Lowest clock the clock is stepped down to, in Hz
+    pub min_clock: u32,
+    /// Custom decision after a failed transfer
+    pub decision: Option<RecoveryDecision>,
+}
+
+impl Default for RecoveryPolicy {
+    fn default() -> Self {
+        RecoveryPolicy {
+            command_attempts: 3,
+            crc_errors_before_clock_step_down: 2,
+            failures_before_reinit: 4,
+            max_reinits: 1,
+            min_clock: 400_000,
+            decision: None,
+        }
+    }
+}
+
+impl RecoveryPolicy {
+    /// What to do after a failed transfer
+    /// The state already accounts for the failure
+    pub fn decide(&self, state: &RecoveryState, error: &MciError) -> RecoveryAction {
+        if let Some(decision) = self.decision {
+            return decision(self, state, error);
+        }
+        if !is_retryable(error) {
+            RecoveryAction::Fail
+        } else if state.consecutive_failures >= self.failures_before_reinit {
+            if state.reinits >= self.max_reinits {
+                RecoveryAction::GiveUp
+            } else {
+                RecoveryAction::Reinitialize
+            }
+        } else if state.consecutive_crc_errors >= self.crc_errors_before_clock_step_down {
+            RecoveryAction::StepClockDown
+        } else {
+            RecoveryAction::Retry
+        }
+    }
+}
+
+/// Attempts left of a command that is sent again on CRC and timeout errors
+pub(crate) struct CommandAttempts {
+    left: u8,
+}
+
+impl CommandAttempts {
+    pub(crate) fn new(policy: &RecoveryPolicy) -> Self {
+        CommandAttempts {
+            left: policy.command_attempts.max(1),
+        }
+    }
+
+    /// Whether the command is to be sent again after this error, which uses up an attempt
+    pub(crate) fn retry(&mut self, error: &MciError) -> bool {
+        if self.left > 1 && is_retryable(error) {
+            self.left -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Whether an error may go away by trying again: a line error, a timeout or a busy host
+pub fn is_retryable(error: &MciError) -> bool {
+    matches!(
+        error,
+        MciError::CommandError(_)
+            | MciError::DataError(_)
+            | MciError::ReadError
+            | MciError::WriteError
+            | MciError::CommandInhibited
+            | MciError::Impl(ImplError::TimedOut)
+    )
+}
+
+/// Whether an error is a CRC error on the command or data line
+pub fn is_crc_error(error: &MciError) -> bool {
+    matches!(
+        error,
+        MciError::CommandError(CommandOrDataError::Crc)
+            | MciError::DataError(CommandOrDataError::Crc)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRC: MciError = MciError::DataError(CommandOrDataError::Crc);
+    const TIMED_OUT: MciError = MciError::Impl(ImplError::TimedOut);
+
+    fn state_after(errors: &[MciError]) -> RecoveryState {
+        let mut state = RecoveryState::default();
+        for error in errors {
+            state.failed(error);
+        }
+        state
+    }
+
+    #[test]
+    fn errors_that_do_not_go_away_fail() {
+        let policy = RecoveryPolicy::default();
+        let state = state_after(&[MciError::WriteProtected]);
+        assert_eq!(
+            policy.decide(&state, &MciError::WriteProtected),
+            RecoveryAction::Fail
+        );
+    }
+
+    #[test]
+    fn a_single_failure_is_retried() {
+        let policy = RecoveryPolicy::default();
+        assert_eq!(
+            policy.decide(&state_after(&[CRC]), &CRC),
+            RecoveryAction::Retry
+        );
+        assert_eq!(
+            policy.decide(&state_after(&[TIMED_OUT]), &TIMED_OUT),
+            RecoveryAction::Retry
+        );
+    }
+
+    #[test]
+    fn crc_errors_in_a_row_step_the_clock_down() {
+        let policy = RecoveryPolicy::default();
+        assert_eq!(
+            policy.decide(&state_after(&[CRC, CRC]), &CRC),
+            RecoveryAction::StepClockDown
+        );
+        // A timeout in between starts the CRC count over
+        assert_eq!(
+            policy.decide(&state_after(&[CRC, TIMED_OUT, CRC]), &CRC),
+            RecoveryAction::Retry
+        );
+    }
+
+    #[test]
+    fn failures_in_a_row_reinitialize_then_give_up() {
+        let policy = RecoveryPolicy::default();
+        let mut state = state_after(&[TIMED_OUT; 4]);
+        assert_eq!(
+            policy.decide(&state, &TIMED_OUT),
+            RecoveryAction::Reinitialize
+        );
+        state.reinits = policy.max_reinits;
+        assert_eq!(policy.decide(&state, &TIMED_OUT), RecoveryAction::GiveUp);
+    }
+
+    #[test]
+    fn a_success_forgets_the_failures() {
+        let policy = RecoveryPolicy::default();
+        let mut state = state_after(&[CRC; 3]);
+        state.succeeded();
+        state.failed(&CRC);
+        assert_eq!(policy.decide(&state, &CRC), RecoveryAction::Retry);
+    }
+
+    #[test]
+    fn a_custom_decision_replaces_the_default() {
+        let policy = RecoveryPolicy {
+            decision: Some(|_, _, _| RecoveryAction::GiveUp),
+            ..RecoveryPolicy::default()
+        };
+        assert_eq!(
+            policy.decide(&state_after(&[CRC]), &CRC),
+            RecoveryAction::GiveUp
+        );
+    }
+}
@@ -86,6 +86,24 @@ impl CsdRegister {
         self.val.get_bits(122..126) as u8
     }
 
+    pub fn set_read_access_time(&mut self, taac: u8) {
+        self.val.set_bits(112..120, taac as u32);
+    }
+
+    /// TAAC, time dependent part of the read access time
+    pub fn read_access_time(&self) -> u8 {
+        self.val.get_bits(112..120) as u8
+    }
+
+    pub fn set_read_access_clock_cycles(&mut self, nsac: u8) {
+        self.val.set_bits(104..112, nsac as u32);
+    }
+
+    /// NSAC, clock dependent part of the read access time, in units of 100 clock cycles
+    pub fn read_access_clock_cycles(&self) -> u8 {
+        self.val.get_bits(104..112) as u8
+    }
+
     pub fn set_transmission_speed(&mut self, speed: u8) {
         self.val.set_bits(96..104, speed as u32);
     }
@@ -111,11 +129,11 @@ impl CsdRegister {
     }
 
     pub fn set_sd_2_0_card_size(&mut self, size: u32) {
-        self.val.set_bits(48..70, size as u32);
+        self.val.set_bits(48..70, size);
     }
 
     pub fn sd_2_0_card_size(&self) -> u32 {
-        self.val.get_bits(48..70) as u32
+        self.val.get_bits(48..70)
     }
 
     pub fn set_card_size_multiplier(&mut self, multiplier: u8) {
@@ -125,4 +143,13 @@ impl CsdRegister {
     pub fn card_size_multiplier(&self) -> u8 {
         self.val.get_bits(47..50) as u8
     }
+
+    pub fn set_write_speed_factor(&mut self, factor: u8) {
+        self.val.set_bits(26..29, factor as u32);
+    }
+
+    /// R2W_FACTOR, the write time is the read access time times 2^factor
+    pub fn write_speed_factor(&self) -> u8 {
+        self.val.get_bits(26..29) as u8
+    }
 }
@@ -1,5 +1,5 @@
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
 pub struct OcrRegister {
     pub val: u32,
@@ -13,12 +13,15 @@ pub enum AccessMode {
     Sector = 2,
 }
 
-impl From<u32> for AccessMode {
-    fn from(val: u32) -> Self {
+impl TryFrom<u32> for AccessMode {
+    type Error = u32;
+
+    /// The reserved access modes 1 and 3 are given back as the error
+    fn try_from(val: u32) -> Result<Self, Self::Error> {
         match val {
-            0 => AccessMode::Byte,
-            2 => AccessMode::Sector,
-            _ => unsafe { unreachable_unchecked() },
+            0 => Ok(AccessMode::Byte),
+            2 => Ok(AccessMode::Sector),
+            _ => Err(val),
         }
     }
 }
@@ -210,15 +213,20 @@ impl OcrRegister {
         self.val.get_bit(28)
     }
 
+    /// Amount of I/O functions - SDIO card
+    pub fn io_functions_amount(&self) -> u8 {
+        self.val.get_bits(28..=30) as u8
+    }
+
     /// Set access mode - MMC card
     pub fn set_access_mode(&mut self, mode: AccessMode) -> &mut Self {
         self.val.set_bits(29..=30, mode as u32);
         self
     }
 
-    /// Access mode - MMC card
-    pub fn access_mode(&self) -> AccessMode {
-        self.val.get_bits(29..=30).into()
+    /// Access mode - MMC card, None for a reserved mode
+    pub fn access_mode(&self) -> Option<AccessMode> {
+        AccessMode::try_from(self.val.get_bits(29..=30)).ok()
     }
 
     /// Set card capacity status bit - SD card
@@ -1,5 +1,5 @@
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
 pub enum CardStatusState {
     Idle = 0,
@@ -13,23 +13,27 @@ pub enum CardStatusState {
     Disabled = 8,
 }
 
-impl From<u32> for CardStatusState {
-    fn from(val: u32) -> Self {
+impl TryFrom<u32> for CardStatusState {
+    type Error = u32;
+
+    /// The reserved states 9 to 15 are given back as the error
+    fn try_from(val: u32) -> Result<Self, Self::Error> {
         match val {
-            0 => CardStatusState::Idle,
-            1 => CardStatusState::Ready,
-            2 => CardStatusState::Identity,
-            3 => CardStatusState::Standby,
-            4 => CardStatusState::Transmitting,
-            6 => CardStatusState::Data,
-            7 => CardStatusState::Programming,
-            8 => CardStatusState::Disabled,
-            _ => unsafe { unreachable_unchecked() },
+            0 => Ok(CardStatusState::Idle),
+            1 => Ok(CardStatusState::Ready),
+            2 => Ok(CardStatusState::Identity),
+            3 => Ok(CardStatusState::Standby),
+            4 => Ok(CardStatusState::Transmitting),
+            5 => Ok(CardStatusState::Data),
+            6 => Ok(CardStatusState::Receiving),
+            7 => Ok(CardStatusState::Programming),
+            8 => Ok(CardStatusState::Disabled),
+            _ => Err(val),
         }
     }
 }
 
-#[derive(Default)]
+#[derive(Copy, Clone, Default)]
 pub struct CardStatusRegister {
     pub val: u32,
 }
@@ -63,8 +67,9 @@ impl CardStatusRegister {
         self.val.set_bits(9..13, state as u32);
     }
 
-    pub fn state(&self) -> CardStatusState {
-        self.val.get_bits(9..13).into()
+    /// None for a reserved state
+    pub fn state(&self) -> Option<CardStatusState> {
+        CardStatusState::try_from(self.val.get_bits(9..13)).ok()
     }
 
     pub fn set_erase_reset(&mut self, set: bool) {
@@ -216,7 +221,9 @@ impl CardStatusRegister {
             | self.address_misalign_error()
             | self.block_length_error()
             | self.write_protect_violation()
+            | self.unlock_failed()
             | self.illegal_command()
+            | self.card_ecc_failed()
             | self.cc_error()
             | self.status_error()
     }
@@ -2,7 +2,7 @@ use crate::sd::sd_bus_width::SdBusWidth;
 use crate::sd::sd_physical_specification::SdPhysicalSpecification;
 use crate::sd::sd_security::SdSecurity;
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
 pub struct ScrRegister {
     pub val: u64,
@@ -10,15 +10,9 @@ pub struct ScrRegister {
 
 impl From<[u8; 8]> for ScrRegister {
     fn from(val: [u8; 8]) -> Self {
+        // The SCR is sent MSB first
         ScrRegister {
-            val: (val[0] as u64)
-                + ((val[1] as u64) << 8)
-                + ((val[2] as u64) << 16)
-                + ((val[3] as u64) << 24)
-                + ((val[4] as u64) << 32)
-                + ((val[5] as u64) << 40)
-                + ((val[6] as u64) << 48)
-                + ((val[7] as u64) << 56),
+            val: u64::from_be_bytes(val),
         }
     }
 }
@@ -27,11 +21,14 @@ pub enum ScrRegisterStructureVersion {
     Version1_0 = 0,
 }
 
-impl From<u64> for ScrRegisterStructureVersion {
-    fn from(val: u64) -> Self {
+impl TryFrom<u64> for ScrRegisterStructureVersion {
+    type Error = u64;
+
+    /// A structure version newer than this crate knows is given back as the error
+    fn try_from(val: u64) -> Result<Self, Self::Error> {
         match val {
-            0 => ScrRegisterStructureVersion::Version1_0,
-            _ => unsafe { unreachable_unchecked() },
+            0 => Ok(ScrRegisterStructureVersion::Version1_0),
+            _ => Err(val),
         }
     }
 }
@@ -41,16 +38,18 @@ impl ScrRegister {
         self.val.set_bits(60..=63, version as u64);
     }
 
-    pub fn structure_version(&self) -> ScrRegisterStructureVersion {
-        self.val.get_bits(60..=63).into()
+    /// None for a structure version that is not known
+    pub fn structure_version(&self) -> Option<ScrRegisterStructureVersion> {
+        ScrRegisterStructureVersion::try_from(self.val.get_bits(60..=63)).ok()
     }
 
     pub fn set_sd_specification_version(&mut self, version: SdPhysicalSpecification) {
         self.val.set_bits(56..=59, version as u64);
     }
 
-    pub fn sd_specification_version(&self) -> SdPhysicalSpecification {
-        (self.val.get_bits(56..=59) as u8).into()
+    /// None for a specification version that is not known
+    pub fn sd_specification_version(&self) -> Option<SdPhysicalSpecification> {
+        SdPhysicalSpecification::try_from(self.val.get_bits(56..=59) as u8).ok()
     }
 
     pub fn set_data_status_after_erase(&mut self, enabled: bool) {
@@ -65,16 +64,18 @@ impl ScrRegister {
         self.val.set_bits(52..=54, version as u64);
     }
 
-    pub fn sd_security_version(&self) -> SdSecurity {
-        (self.val.get_bits(52..=54) as u8).into()
+    /// None for a security version that is not known
+    pub fn sd_security_version(&self) -> Option<SdSecurity> {
+        SdSecurity::try_from(self.val.get_bits(52..=54) as u8).ok()
     }
 
     pub fn set_sd_bus_width(&mut self, bus_width: SdBusWidth) {
         self.val.set_bits(48..=51, bus_width as u64);
     }
 
-    pub fn sd_bus_width(&self) -> SdBusWidth {
-        (self.val.get_bits(48..=51) as u8).into()
+    /// None for a bus width that is not known
+    pub fn sd_bus_width(&self) -> Option<SdBusWidth> {
+        SdBusWidth::try_from(self.val.get_bits(48..=51) as u8).ok()
     }
 
     pub fn set_is_spec3(&mut self, spec3: bool) {
@@ -100,4 +101,9 @@ impl ScrRegister {
     pub fn sd_command_support(&self) -> u8 {
         self.val.get_bits(32..=33) as u8
     }
+
+    /// CMD23 (SET_BLOCK_COUNT) is supported
+    pub fn supports_set_block_count(&self) -> bool {
+        self.val.get_bit(33)
+    }
 }
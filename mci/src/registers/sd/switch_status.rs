@@ -22,7 +22,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn max_current_consumption(&self) -> u16 {
-        self.val.get_bits(496..512) as u16
+        self.val.get_bits(496..512)
     }
 
     pub fn set_group6_info_status(&mut self, val: u16) {
@@ -30,7 +30,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group6_info_status(&self) -> u16 {
-        self.val.get_bits(480..496) as u16
+        self.val.get_bits(480..496)
     }
 
     pub fn set_group5_info_status(&mut self, val: u16) {
@@ -38,7 +38,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group5_info_status(&self) -> u16 {
-        self.val.get_bits(464..480) as u16
+        self.val.get_bits(464..480)
     }
 
     pub fn set_group4_info_status(&mut self, val: u16) {
@@ -46,7 +46,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group4_info_status(&self) -> u16 {
-        self.val.get_bits(448..464) as u16
+        self.val.get_bits(448..464)
     }
 
     pub fn set_group3_info_status(&mut self, val: u16) {
@@ -54,7 +54,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group3_info_status(&self) -> u16 {
-        self.val.get_bits(432..448) as u16
+        self.val.get_bits(432..448)
     }
 
     pub fn set_group1_info_status(&mut self, val: u16) {
@@ -62,7 +62,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group1_info_status(&self) -> u16 {
-        self.val.get_bits(416..432) as u16
+        self.val.get_bits(416..432)
     }
 
     pub fn set_group6_rc(&mut self, val: u8) {
@@ -126,7 +126,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group6_busy(&self) -> u16 {
-        self.val.get_bits(352..368) as u16
+        self.val.get_bits(352..368)
     }
 
     pub fn set_group5_busy(&mut self, val: u16) {
@@ -134,7 +134,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group5_busy(&self) -> u16 {
-        self.val.get_bits(336..352) as u16
+        self.val.get_bits(336..352)
     }
 
     pub fn set_group4_busy(&mut self, val: u16) {
@@ -142,7 +142,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group4_busy(&self) -> u16 {
-        self.val.get_bits(320..336) as u16
+        self.val.get_bits(320..336)
     }
 
     pub fn set_group3_busy(&mut self, val: u16) {
@@ -150,7 +150,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group3_busy(&self) -> u16 {
-        self.val.get_bits(304..320) as u16
+        self.val.get_bits(304..320)
     }
 
     pub fn set_group2_busy(&mut self, val: u16) {
@@ -158,7 +158,7 @@ impl SwitchStatusRegister {
     }
 
     pub fn group2_busy(&self) -> u16 {
-        self.val.get_bits(288..304) as u16
+        self.val.get_bits(288..304)
     }
 
     pub fn set_group1_busy(&mut self, val: u16) {
@@ -166,6 +166,6 @@ impl SwitchStatusRegister {
     }
 
     pub fn group1_busy(&self) -> u16 {
-        self.val.get_bits(272..288) as u16
+        self.val.get_bits(272..288)
     }
 }
@@ -0,0 +1,28 @@
+//! SDIO Type-A Bluetooth transport registers, in the function's register space
+
+/// Receive data port, read with fixed address CMD53
+pub const SDIO_BT_RECEIVE_DATA: u8 = 0x00;
+/// Transmit data port, written with fixed address CMD53
+pub const SDIO_BT_TRANSMIT_DATA: u8 = 0x00;
+/// Read packet control (RPC). Acknowledges the received packet or asks for it again
+pub const SDIO_BT_READ_PACKET_CONTROL: u8 = 0x10;
+/// Write packet control (WPC). Asks the card to drop a partly sent packet before it is sent again
+pub const SDIO_BT_WRITE_PACKET_CONTROL: u8 = 0x11;
+/// Retry control status (read) and set (write)
+pub const SDIO_BT_RETRY_CONTROL: u8 = 0x12;
+/// Interrupt indication (read) and clear (write)
+pub const SDIO_BT_INTERRUPT: u8 = 0x13;
+/// Interrupt enable
+pub const SDIO_BT_INTERRUPT_ENABLE: u8 = 0x14;
+/// Bluetooth mode status (read) and set (write)
+pub const SDIO_BT_MODE: u8 = 0x20;
+
+/// Packet control: packet read or written successfully
+pub const SDIO_BT_PACKET_CONTROL_ACK: u8 = 0x00;
+/// Packet control: transfer the packet again
+pub const SDIO_BT_PACKET_CONTROL_RETRY: u8 = 0x01;
+/// Interrupt bit: a packet is ready to be read
+pub const SDIO_BT_INTERRUPT_PACKET_READY: u8 = 0x01;
+
+/// Size of the Type-A header: 3 bytes of packet length, header included, and the service ID
+pub const SDIO_BT_HEADER_SIZE: usize = 4;
@@ -1,6 +1,7 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
 pub enum BusWidth {
     /// 1-bit data bus
@@ -9,16 +10,20 @@ pub enum BusWidth {
     _4bit = 2,
 }
 
-impl From<u8> for BusWidth {
-    fn from(val: u8) -> Self {
+impl TryFrom<u8> for BusWidth {
+    type Error = u8;
+
+    /// The reserved bus widths 1 and 3 are given back as the error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            0 => BusWidth::_1bit,
-            2 => BusWidth::_4bit,
-            _ => unsafe { unreachable_unchecked() },
+            0 => Ok(BusWidth::_1bit),
+            2 => Ok(BusWidth::_4bit),
+            _ => Err(val),
         }
     }
 }
 
+#[derive(Copy, Clone)]
 pub struct BusInterfaceControlRegister {
     pub val: u8,
 }
@@ -31,11 +36,12 @@ impl RegisterAddress for BusInterfaceControlRegister {
 
 impl BusInterfaceControlRegister {
     pub fn set_bus_width(&mut self, width: BusWidth) {
-        self.val.set_bits(0..1, width as u8);
+        self.val.set_bits(0..2, width as u8);
     }
 
-    pub fn bus_width(&mut self) -> BusWidth {
-        self.val.get_bits(0..1).into()
+    /// None for a reserved bus width
+    pub fn bus_width(&self) -> Option<BusWidth> {
+        BusWidth::try_from(self.val.get_bits(0..2)).ok()
     }
 
     pub fn set_enable_continuous_spi_interrupt(&mut self, enabled: bool) {
@@ -62,3 +68,16 @@ impl BusInterfaceControlRegister {
         self.val.get_bit(7)
     }
 }
+
+impl SdioRegister for BusInterfaceControlRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0xA3;
+
+    fn from_val(val: u8) -> Self {
+        BusInterfaceControlRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
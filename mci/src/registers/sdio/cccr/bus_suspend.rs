@@ -1,4 +1,5 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
 pub struct BusSuspendRegister {
@@ -12,6 +13,7 @@ impl RegisterAddress for BusSuspendRegister {
 }
 
 impl BusSuspendRegister {
+    /// Bus status (BS). The selected function is using the DAT lines
     pub fn set_transfer_on_datx_line(&mut self, transferring: bool) {
         self.val.set_bit(0, transferring);
     }
@@ -20,11 +22,25 @@ impl BusSuspendRegister {
         self.val.get_bit(0)
     }
 
+    /// Bus release request (BR). Stays set until the selected function is suspended
     pub fn set_request_release_status(&mut self, release: bool) {
         self.val.set_bit(1, release);
     }
 
     pub fn request_release_status(&self) -> bool {
-        self.val.get_bit(0)
+        self.val.get_bit(1)
+    }
+}
+
+impl SdioRegister for BusSuspendRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        BusSuspendRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
     }
 }
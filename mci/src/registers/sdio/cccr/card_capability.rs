@@ -1,6 +1,8 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
+#[derive(Copy, Clone)]
 pub struct CardCapabilityRegister {
     pub val: u8,
 }
@@ -79,3 +81,16 @@ impl CardCapabilityRegister {
         self.val.get_bit(7)
     }
 }
+
+impl SdioRegister for CardCapabilityRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x20;
+
+    fn from_val(val: u8) -> Self {
+        CardCapabilityRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
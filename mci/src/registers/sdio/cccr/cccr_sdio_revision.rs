@@ -1,6 +1,7 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
 pub enum CccrRevision {
     /// CCCR/FBR revision 1.00
@@ -13,14 +14,17 @@ pub enum CccrRevision {
     Revision3_00 = 3,
 }
 
-impl From<u8> for CccrRevision {
-    fn from(val: u8) -> Self {
+impl TryFrom<u8> for CccrRevision {
+    type Error = u8;
+
+    /// A revision newer than this crate knows is given back as the error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            0 => CccrRevision::Revision1_00,
-            1 => CccrRevision::Revision1_10,
-            2 => CccrRevision::Revision2_00,
-            3 => CccrRevision::Revision3_00,
-            _ => unsafe { unreachable_unchecked() },
+            0 => Ok(CccrRevision::Revision1_00),
+            1 => Ok(CccrRevision::Revision1_10),
+            2 => Ok(CccrRevision::Revision2_00),
+            3 => Ok(CccrRevision::Revision3_00),
+            _ => Err(val),
         }
     }
 }
@@ -38,19 +42,23 @@ pub enum SdioSpecification {
     Specification3_00 = 4,
 }
 
-impl From<u8> for SdioSpecification {
-    fn from(val: u8) -> Self {
+impl TryFrom<u8> for SdioSpecification {
+    type Error = u8;
+
+    /// A version newer than this crate knows is given back as the error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            0 => SdioSpecification::Specification1_00,
-            1 => SdioSpecification::Specification1_10,
-            2 => SdioSpecification::Specification1_20,
-            3 => SdioSpecification::Specification2_00,
-            4 => SdioSpecification::Specification3_00,
-            _ => unsafe { unreachable_unchecked() },
+            0 => Ok(SdioSpecification::Specification1_00),
+            1 => Ok(SdioSpecification::Specification1_10),
+            2 => Ok(SdioSpecification::Specification1_20),
+            3 => Ok(SdioSpecification::Specification2_00),
+            4 => Ok(SdioSpecification::Specification3_00),
+            _ => Err(val),
         }
     }
 }
 
+#[derive(Copy, Clone)]
 pub struct CccrSdioRevisionRegister {
     pub val: u8,
 }
@@ -63,18 +71,43 @@ impl RegisterAddress for CccrSdioRevisionRegister {
 
 impl CccrSdioRevisionRegister {
     pub fn set_cccr_revision(&mut self, revision: CccrRevision) {
-        self.val.set_bits(0..3, revision as u8);
+        self.val.set_bits(0..4, revision as u8);
+    }
+
+    /// None for a CCCR revision that is not known
+    pub fn cccr_revision(&self) -> Option<CccrRevision> {
+        CccrRevision::try_from(self.val.get_bits(0..4)).ok()
     }
 
-    pub fn cccr_revision(&self) -> CccrRevision {
-        self.val.get_bits(0..3).into()
+    /// Whether the CCCR revision is one that is known
+    pub fn cccr_revision_supported(&self) -> bool {
+        self.cccr_revision().is_some()
     }
 
     pub fn set_sdio_specification_version(&mut self, version: SdioSpecification) {
-        self.val.set_bits(4..7, version as u8);
+        self.val.set_bits(4..8, version as u8);
+    }
+
+    /// None for an SDIO specification version that is not known
+    pub fn sdio_specification_verison(&self) -> Option<SdioSpecification> {
+        SdioSpecification::try_from(self.val.get_bits(4..8)).ok()
+    }
+
+    /// Whether the SDIO specification version is one that is known
+    pub fn sdio_specification_supported(&self) -> bool {
+        self.sdio_specification_verison().is_some()
+    }
+}
+
+impl SdioRegister for CccrSdioRevisionRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        CccrSdioRevisionRegister { val }
     }
 
-    pub fn sdio_specification_verison(&self) -> SdioSpecification {
-        self.val.get_bits(4..7).into()
+    fn val(&self) -> u8 {
+        self.val
     }
 }
@@ -0,0 +1,63 @@
+use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
+use bit_field::BitField;
+
+/// Driver strength of the card. Reserved before CCCR revision 3.00
+#[derive(Copy, Clone)]
+pub struct DriverStrengthRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for DriverStrengthRegister {
+    fn address() -> u8 {
+        0x15
+    }
+}
+
+impl DriverStrengthRegister {
+    pub fn set_supports_driver_type_a(&mut self, supports: bool) {
+        self.val.set_bit(0, supports);
+    }
+
+    pub fn supports_driver_type_a(&self) -> bool {
+        self.val.get_bit(0)
+    }
+
+    pub fn set_supports_driver_type_c(&mut self, supports: bool) {
+        self.val.set_bit(1, supports);
+    }
+
+    pub fn supports_driver_type_c(&self) -> bool {
+        self.val.get_bit(1)
+    }
+
+    pub fn set_supports_driver_type_d(&mut self, supports: bool) {
+        self.val.set_bit(2, supports);
+    }
+
+    pub fn supports_driver_type_d(&self) -> bool {
+        self.val.get_bit(2)
+    }
+
+    /// Driver type select (DTSx). 0 is type B, 1 type A, 2 type C and 3 type D
+    pub fn set_driver_type_select(&mut self, driver_type: u8) {
+        self.val.set_bits(4..6, driver_type);
+    }
+
+    pub fn driver_type_select(&self) -> u8 {
+        self.val.get_bits(4..6)
+    }
+}
+
+impl SdioRegister for DriverStrengthRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x30;
+
+    fn from_val(val: u8) -> Self {
+        DriverStrengthRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -1,4 +1,6 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
 pub struct ExecutionFlagsRegister {
@@ -12,6 +14,15 @@ impl RegisterAddress for ExecutionFlagsRegister {
 }
 
 impl ExecutionFlagsRegister {
+    /// Function 1 to 7, or the memory, is executing a command (EXx)
+    pub fn function_executing(&self, function: FunctionSelection) -> bool {
+        match function {
+            FunctionSelection::MemoryInCard => self.val.get_bit(0),
+            FunctionSelection::FunctionCia0 => false,
+            function => self.val.get_bit(function as usize),
+        }
+    }
+
     pub fn set_executing_status_of_memory(&mut self, executing: bool) {
         self.val.set_bit(0, executing);
     }
@@ -76,3 +87,16 @@ impl ExecutionFlagsRegister {
         self.val.get_bit(7)
     }
 }
+
+impl SdioRegister for ExecutionFlagsRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        ExecutionFlagsRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
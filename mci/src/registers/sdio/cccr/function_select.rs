@@ -1,7 +1,9 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
+#[derive(Copy, Clone, PartialEq)]
 pub enum FunctionSelection {
     FunctionCia0 = 0,
     Function1 = 1,
@@ -14,19 +16,29 @@ pub enum FunctionSelection {
     MemoryInCard = 8,
 }
 
-impl From<u8> for FunctionSelection {
-    fn from(val: u8) -> Self {
+impl FunctionSelection {
+    /// Functions reached by CMD52 and CMD53, indexed by function number
+    pub const FUNCTIONS: [FunctionSelection; 8] = [
+        FunctionSelection::FunctionCia0,
+        FunctionSelection::Function1,
+        FunctionSelection::Function2,
+        FunctionSelection::Function3,
+        FunctionSelection::Function4,
+        FunctionSelection::Function5,
+        FunctionSelection::Function6,
+        FunctionSelection::Function7,
+    ];
+}
+
+impl TryFrom<u8> for FunctionSelection {
+    type Error = u8;
+
+    /// The reserved values 9 to 15 are given back as the error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            0 => FunctionSelection::FunctionCia0,
-            1 => FunctionSelection::Function1,
-            2 => FunctionSelection::Function2,
-            3 => FunctionSelection::Function3,
-            4 => FunctionSelection::Function4,
-            5 => FunctionSelection::Function5,
-            6 => FunctionSelection::Function6,
-            7 => FunctionSelection::Function7,
-            8 => FunctionSelection::MemoryInCard,
-            _ => unsafe { unreachable_unchecked() },
+            0..=7 => Ok(FunctionSelection::FUNCTIONS[val as usize]),
+            8 => Ok(FunctionSelection::MemoryInCard),
+            _ => Err(val),
         }
     }
 }
@@ -42,6 +54,7 @@ impl RegisterAddress for FunctionSelectionRegister {
 }
 
 impl FunctionSelectionRegister {
+    /// Resume data flag (DF). The resumed function has data to transfer
     pub fn set_resume_data_flag(&mut self, resume: bool) {
         self.val.set_bit(7, resume);
     }
@@ -51,10 +64,24 @@ impl FunctionSelectionRegister {
     }
 
     pub fn set_function_selection(&mut self, selection: FunctionSelection) {
-        self.val.set_bits(0..3, selection as u8);
+        self.val.set_bits(0..4, selection as u8);
+    }
+
+    /// None for a reserved value
+    pub fn function_selection(&self) -> Option<FunctionSelection> {
+        FunctionSelection::try_from(self.val.get_bits(0..4)).ok()
+    }
+}
+
+impl SdioRegister for FunctionSelectionRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x0F;
+
+    fn from_val(val: u8) -> Self {
+        FunctionSelectionRegister { val }
     }
 
-    pub fn function_selection(&self) -> FunctionSelection {
-        self.val.get_bits(0..3).into()
+    fn val(&self) -> u8 {
+        self.val
     }
 }
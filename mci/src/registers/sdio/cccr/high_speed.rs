@@ -1,6 +1,8 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
+#[derive(Copy, Clone)]
 pub struct HighSpeedRegister {
     pub val: u8,
 }
@@ -28,3 +30,16 @@ impl HighSpeedRegister {
         self.val.get_bit(1)
     }
 }
+
+impl SdioRegister for HighSpeedRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x02;
+
+    fn from_val(val: u8) -> Self {
+        HighSpeedRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -1,6 +1,9 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
+#[derive(Copy, Clone)]
 pub struct InterruptEnableRegister {
     pub val: u8,
 }
@@ -20,6 +23,16 @@ impl InterruptEnableRegister {
         self.val.get_bit(0)
     }
 
+    /// Set if the interrupt of function 1 to 7 is enabled
+    pub fn set_function_interrupt_enabled(&mut self, function: FunctionSelection, enabled: bool) {
+        self.val.set_bit(function as usize, enabled);
+    }
+
+    /// The interrupt of function 1 to 7 is enabled
+    pub fn function_interrupt_enabled(&self, function: FunctionSelection) -> bool {
+        self.val.get_bit(function as usize)
+    }
+
     pub fn set_function1_interrupt_enabled(&mut self, enabled: bool) {
         self.val.set_bit(1, enabled);
     }
@@ -76,3 +89,16 @@ impl InterruptEnableRegister {
         self.val.get_bit(7)
     }
 }
+
+impl SdioRegister for InterruptEnableRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0xFF;
+
+    fn from_val(val: u8) -> Self {
+        InterruptEnableRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -1,4 +1,6 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
 pub struct InterruptPendingRegister {
@@ -12,6 +14,11 @@ impl RegisterAddress for InterruptPendingRegister {
 }
 
 impl InterruptPendingRegister {
+    /// Function 1 to 7 has an interrupt pending
+    pub fn function_interrupt_pending(&self, function: FunctionSelection) -> bool {
+        self.val.get_bit(function as usize)
+    }
+
     pub fn set_function1_interrupt_pending(&mut self, pending: bool) {
         self.val.set_bit(1, pending);
     }
@@ -68,3 +75,16 @@ impl InterruptPendingRegister {
         self.val.get_bit(7)
     }
 }
+
+impl SdioRegister for InterruptPendingRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        InterruptPendingRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -1,4 +1,6 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
 pub struct IoAbortRegister {
@@ -12,62 +14,72 @@ impl RegisterAddress for IoAbortRegister {
 }
 
 impl IoAbortRegister {
+    /// Abort the CMD53 transfer of function 1 to 7 (ASx)
+    pub fn set_abort_function(&mut self, function: FunctionSelection) {
+        self.val.set_bits(0..3, function as u8);
+    }
+
+    pub fn abort_function(&self) -> FunctionSelection {
+        FunctionSelection::FUNCTIONS[self.val.get_bits(0..3) as usize]
+    }
+
     pub fn set_function1_abort(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 1 } else { 0 });
+        self.val.set_bits(0..3, if abort { 1 } else { 0 });
     }
 
     pub fn function1_abort(&mut self) -> bool {
-        self.val.get_bits(0..2) == 1
+        self.val.get_bits(0..3) == 1
     }
 
     pub fn set_function2_abort(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 2 } else { 0 });
+        self.val.set_bits(0..3, if abort { 2 } else { 0 });
     }
 
     pub fn function2_abort(&mut self) -> bool {
-        self.val.get_bits(0..2) == 2
+        self.val.get_bits(0..3) == 2
     }
 
     pub fn set_function3_abort(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 3 } else { 0 });
+        self.val.set_bits(0..3, if abort { 3 } else { 0 });
     }
 
     pub fn function3_abort(&mut self) -> bool {
-        self.val.get_bits(0..2) == 3
+        self.val.get_bits(0..3) == 3
     }
 
     pub fn set_function4_abort(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 4 } else { 0 });
+        self.val.set_bits(0..3, if abort { 4 } else { 0 });
     }
 
     pub fn function4_abort(&mut self) -> bool {
-        self.val.get_bits(0..2) == 4
+        self.val.get_bits(0..3) == 4
     }
 
     pub fn set_function5_abort(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 5 } else { 0 });
+        self.val.set_bits(0..3, if abort { 5 } else { 0 });
     }
 
     pub fn function5_abort(&mut self) -> bool {
-        self.val.get_bits(0..2) == 5
+        self.val.get_bits(0..3) == 5
     }
 
     pub fn set_function6_abort(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 6 } else { 0 });
+        self.val.set_bits(0..3, if abort { 6 } else { 0 });
     }
 
     pub fn function6_abort(&mut self) -> bool {
-        self.val.get_bits(0..2) == 6
+        self.val.get_bits(0..3) == 6
     }
 
     pub fn set_function7_abort(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 7 } else { 0 });
+        self.val.set_bits(0..3, if abort { 7 } else { 0 });
     }
 
     pub fn function7_abort(&mut self) -> bool {
-        self.val.get_bits(0..2) == 7
+        self.val.get_bits(0..3) == 7
     }
 
+    /// Reset the IO portion of the card (RES). The bit is write only
     pub fn set_card_reset(&mut self, reset: bool) {
         self.val.set_bit(3, reset);
     }
@@ -77,10 +89,23 @@ impl IoAbortRegister {
     }
 
     pub fn set_abort_select_in_order(&mut self, abort: bool) {
-        self.val.set_bits(0..2, if abort { 7 } else { 0 });
+        self.val.set_bits(0..3, if abort { 7 } else { 0 });
     }
 
     pub fn abort_select_in_order(&self) -> bool {
-        self.val.get_bits(0..2) == 7
+        self.val.get_bits(0..3) == 7
+    }
+}
+
+impl SdioRegister for IoAbortRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        IoAbortRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
     }
 }
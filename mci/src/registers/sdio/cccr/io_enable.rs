@@ -1,6 +1,9 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
+#[derive(Copy, Clone)]
 pub struct IoEnableRegister {
     pub val: u8,
 }
@@ -12,6 +15,16 @@ impl RegisterAddress for IoEnableRegister {
 }
 
 impl IoEnableRegister {
+    /// Set if function 1 to 7 is enabled
+    pub fn set_function_enabled(&mut self, function: FunctionSelection, enabled: bool) {
+        self.val.set_bit(function as usize, enabled);
+    }
+
+    /// Function 1 to 7 is enabled
+    pub fn function_enabled(&self, function: FunctionSelection) -> bool {
+        self.val.get_bit(function as usize)
+    }
+
     pub fn set_function1_enabled(&mut self, enabled: bool) {
         self.val.set_bit(1, enabled);
     }
@@ -68,3 +81,16 @@ impl IoEnableRegister {
         self.val.get_bit(7)
     }
 }
+
+impl SdioRegister for IoEnableRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0xFE;
+
+    fn from_val(val: u8) -> Self {
+        IoEnableRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -1,6 +1,9 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
+#[derive(Copy, Clone)]
 pub struct IoReadyRegister {
     pub val: u8,
 }
@@ -12,6 +15,16 @@ impl RegisterAddress for IoReadyRegister {
 }
 
 impl IoReadyRegister {
+    /// Set if function 1 to 7 is ready
+    pub fn set_function_ready(&mut self, function: FunctionSelection, ready: bool) {
+        self.val.set_bit(function as usize, ready);
+    }
+
+    /// Function 1 to 7 is ready to operate
+    pub fn function_ready(&self, function: FunctionSelection) -> bool {
+        self.val.get_bit(function as usize)
+    }
+
     pub fn set_function1_ready(&mut self, ready: bool) {
         self.val.set_bit(1, ready);
     }
@@ -68,3 +81,16 @@ impl IoReadyRegister {
         self.val.get_bit(7)
     }
 }
+
+impl SdioRegister for IoReadyRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        IoReadyRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -4,6 +4,7 @@ pub mod bus_suspend;
 pub mod card_capability;
 pub mod cccr_sdio_revision;
 pub mod cis_pointer;
+pub mod driver_strength;
 pub mod execution_flags;
 pub mod function_select;
 pub mod high_speed;
@@ -15,3 +16,4 @@ pub mod io_ready;
 pub mod power_control;
 pub mod ready_flags;
 pub mod sd_physical_specification;
+pub mod uhs_support;
@@ -1,6 +1,8 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
+#[derive(Copy, Clone)]
 pub struct PowerControlRegister {
     pub val: u8,
 }
@@ -28,3 +30,16 @@ impl PowerControlRegister {
         self.val.get_bit(1)
     }
 }
+
+impl SdioRegister for PowerControlRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x02;
+
+    fn from_val(val: u8) -> Self {
+        PowerControlRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
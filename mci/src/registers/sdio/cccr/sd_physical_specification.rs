@@ -1,7 +1,10 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use crate::sd::sd_physical_specification::SdPhysicalSpecification;
 use bit_field::BitField;
+use core::convert::TryFrom;
 
+#[derive(Copy, Clone)]
 pub struct SdPhysicalSpecificationRegister {
     pub val: u8,
 }
@@ -17,7 +20,21 @@ impl SdPhysicalSpecificationRegister {
         self.val.set_bits(0..8, val as u8);
     }
 
-    pub fn specification(&self) -> SdPhysicalSpecification {
-        self.val.get_bits(0..8).into()
+    /// None for a specification version that is not known
+    pub fn specification(&self) -> Option<SdPhysicalSpecification> {
+        SdPhysicalSpecification::try_from(self.val.get_bits(0..8)).ok()
+    }
+}
+
+impl SdioRegister for SdPhysicalSpecificationRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        SdPhysicalSpecificationRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
     }
 }
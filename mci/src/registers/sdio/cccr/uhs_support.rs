@@ -0,0 +1,54 @@
+use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
+use bit_field::BitField;
+
+/// UHS-I modes supported by the card. Reserved before CCCR revision 3.00
+#[derive(Copy, Clone)]
+pub struct UhsSupportRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for UhsSupportRegister {
+    fn address() -> u8 {
+        0x14
+    }
+}
+
+impl UhsSupportRegister {
+    pub fn set_supports_sdr50(&mut self, supports: bool) {
+        self.val.set_bit(0, supports);
+    }
+
+    pub fn supports_sdr50(&self) -> bool {
+        self.val.get_bit(0)
+    }
+
+    pub fn set_supports_sdr104(&mut self, supports: bool) {
+        self.val.set_bit(1, supports);
+    }
+
+    pub fn supports_sdr104(&self) -> bool {
+        self.val.get_bit(1)
+    }
+
+    pub fn set_supports_ddr50(&mut self, supports: bool) {
+        self.val.set_bit(2, supports);
+    }
+
+    pub fn supports_ddr50(&self) -> bool {
+        self.val.get_bit(2)
+    }
+}
+
+impl SdioRegister for UhsSupportRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Cccr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        UhsSupportRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
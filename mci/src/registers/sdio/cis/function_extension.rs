@@ -0,0 +1,82 @@
+/// CISTPL_FUNCE of function 0 - Common function extension
+#[derive(Copy, Clone)]
+pub struct CisFunction0Extension {
+    /// Maximum block size of function 0 (TPLFE_FN0_BLK_SIZE)
+    pub block_size: u16,
+    /// Maximum transfer rate, coded as the CSD TRAN_SPEED (TPLFE_MAX_TRAN_SPEED)
+    pub max_transfer_speed: u8,
+}
+
+impl From<&[u8]> for CisFunction0Extension {
+    fn from(body: &[u8]) -> Self {
+        CisFunction0Extension {
+            block_size: u16::from_le_bytes([body[1], body[2]]),
+            max_transfer_speed: body[3],
+        }
+    }
+}
+
+/// CISTPL_FUNCE of functions 1 to 7 - Function extension
+#[derive(Copy, Clone)]
+pub struct CisFunctionExtension {
+    /// Function information, bit 0 set if the function supports wake up (TPLFE_FUNCTION_INFO)
+    pub function_info: u8,
+    /// Version of the standard SDIO function implemented (TPLFE_STD_IO_REV)
+    pub standard_io_revision: u8,
+    /// Product serial number (TPLFE_CARD_PSN)
+    pub card_serial_number: u32,
+    /// Size of the CSA of this function in bytes (TPLFE_CSA_SIZE)
+    pub csa_size: u32,
+    /// CSA properties (TPLFE_CSA_PROPERTY)
+    pub csa_property: u8,
+    /// Maximum block size of this function (TPLFE_MAX_BLK_SIZE)
+    pub max_block_size: u16,
+    /// OCR of this function (TPLFE_OCR)
+    pub ocr: u32,
+    /// Minimum, average and maximum current in operation, in mA (TPLFE_OP_*_PWR)
+    pub operating_power: [u8; 3],
+    /// Minimum, average and maximum current in standby, in mA (TPLFE_SB_*_PWR)
+    pub standby_power: [u8; 3],
+    /// Minimum data transfer bandwidth, in KB/s (TPLFE_MIN_BW)
+    pub min_bandwidth: u16,
+    /// Optimum data transfer bandwidth, in KB/s (TPLFE_OPT_BW)
+    pub optimum_bandwidth: u16,
+    /// Time out for the function to become ready after being enabled, in 10ms units.
+    /// 0 when not given, before SDIO 1.1 (TPLFE_ENABLE_TIMEOUT_VAL)
+    pub enable_timeout: u16,
+}
+
+impl CisFunctionExtension {
+    /// Whether the function can wake up the host
+    pub fn supports_wake_up(&self) -> bool {
+        self.function_info & 0x1 == 0x1
+    }
+
+    /// Whether the CSA may be written to (TPLFE_CSA_PROPERTY bit 0 clear)
+    pub fn csa_writable(&self) -> bool {
+        self.csa_property & 0x1 == 0x0
+    }
+}
+
+impl From<&[u8]> for CisFunctionExtension {
+    fn from(body: &[u8]) -> Self {
+        CisFunctionExtension {
+            function_info: body[1],
+            standard_io_revision: body[2],
+            card_serial_number: u32::from_le_bytes([body[3], body[4], body[5], body[6]]),
+            csa_size: u32::from_le_bytes([body[7], body[8], body[9], body[10]]),
+            csa_property: body[11],
+            max_block_size: u16::from_le_bytes([body[12], body[13]]),
+            ocr: u32::from_le_bytes([body[14], body[15], body[16], body[17]]),
+            operating_power: [body[18], body[19], body[20]],
+            standby_power: [body[21], body[22], body[23]],
+            min_bandwidth: u16::from_le_bytes([body[24], body[25]]),
+            optimum_bandwidth: u16::from_le_bytes([body[26], body[27]]),
+            enable_timeout: if body.len() >= 30 {
+                u16::from_le_bytes([body[28], body[29]])
+            } else {
+                0
+            },
+        }
+    }
+}
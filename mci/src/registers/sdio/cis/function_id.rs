@@ -0,0 +1,20 @@
+/// TPLFID_FUNCTION value of an SDIO card
+pub const TPLFID_FUNCTION_SDIO: u8 = 0x0C;
+
+/// CISTPL_FUNCID - Function identification
+#[derive(Copy, Clone, PartialEq)]
+pub struct CisFunctionId {
+    /// Card function code, 0x0C for SDIO (TPLFID_FUNCTION)
+    pub function: u8,
+    /// System initialization bit mask, not used by SDIO (TPLFID_SYSINIT)
+    pub system_init: u8,
+}
+
+impl From<&[u8]> for CisFunctionId {
+    fn from(body: &[u8]) -> Self {
+        CisFunctionId {
+            function: body[0],
+            system_init: body[1],
+        }
+    }
+}
@@ -0,0 +1,17 @@
+/// CISTPL_MANFID - Manufacturer identification
+#[derive(Copy, Clone, PartialEq)]
+pub struct CisManufacturerId {
+    /// SDIO card manufacturer code (TPLMID_MANF)
+    pub manufacturer: u16,
+    /// Manufacturer information, part number and/or revision (TPLMID_CARD)
+    pub card: u16,
+}
+
+impl From<&[u8]> for CisManufacturerId {
+    fn from(body: &[u8]) -> Self {
+        CisManufacturerId {
+            manufacturer: u16::from_le_bytes([body[0], body[1]]),
+            card: u16::from_le_bytes([body[2], body[3]]),
+        }
+    }
+}
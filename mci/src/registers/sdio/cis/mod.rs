@@ -0,0 +1,69 @@
+pub mod function_extension;
+pub mod function_id;
+pub mod manufacturer_id;
+pub mod tuple_data;
+pub mod version1;
+
+use crate::registers::sdio::cis::function_extension::{
+    CisFunction0Extension, CisFunctionExtension,
+};
+use crate::registers::sdio::cis::function_id::CisFunctionId;
+use crate::registers::sdio::cis::manufacturer_id::CisManufacturerId;
+use crate::registers::sdio::cis::tuple_data::CisTupleData;
+use crate::registers::sdio::cis::version1::CisVersion1;
+
+/// Null tuple, has no link byte
+pub const CISTPL_NULL: u8 = 0x00;
+/// Level 1 version / product information
+pub const CISTPL_VERS_1: u8 = 0x15;
+/// Manufacturer identification
+pub const CISTPL_MANFID: u8 = 0x20;
+/// Function identification
+pub const CISTPL_FUNCID: u8 = 0x21;
+/// Function extension
+pub const CISTPL_FUNCE: u8 = 0x22;
+/// Additional information for SDIO
+pub const CISTPL_SDIO_STD: u8 = 0x91;
+/// Reserved for future SDIO use
+pub const CISTPL_SDIO_EXT: u8 = 0x92;
+/// End of the tuple chain
+pub const CISTPL_END: u8 = 0xFF;
+
+/// FUNCE tuple type of function 0
+pub const TPLFE_TYPE_FUNCTION0: u8 = 0x00;
+/// FUNCE tuple type of functions 1 to 7
+pub const TPLFE_TYPE_FUNCTION: u8 = 0x01;
+
+/// A decoded tuple of a Card Information Structure (CIS)
+pub enum CisTuple {
+    /// CISTPL_MANFID
+    ManufacturerId(CisManufacturerId),
+    /// CISTPL_FUNCID
+    FunctionId(CisFunctionId),
+    /// CISTPL_FUNCE of function 0 (common CIS)
+    Function0Extension(CisFunction0Extension),
+    /// CISTPL_FUNCE of functions 1 to 7
+    FunctionExtension(CisFunctionExtension),
+    /// CISTPL_VERS_1
+    Version1(CisVersion1),
+    /// Any other tuple, vendor specific ones included
+    Raw(CisTupleData),
+}
+
+impl From<CisTupleData> for CisTuple {
+    fn from(tuple: CisTupleData) -> Self {
+        let body = tuple.body();
+        match tuple.code {
+            CISTPL_MANFID if body.len() >= 4 => CisTuple::ManufacturerId(body.into()),
+            CISTPL_FUNCID if body.len() >= 2 => CisTuple::FunctionId(body.into()),
+            CISTPL_FUNCE if body.len() >= 4 && body[0] == TPLFE_TYPE_FUNCTION0 => {
+                CisTuple::Function0Extension(body.into())
+            }
+            CISTPL_FUNCE if body.len() >= 28 && body[0] == TPLFE_TYPE_FUNCTION => {
+                CisTuple::FunctionExtension(body.into())
+            }
+            CISTPL_VERS_1 if body.len() >= 2 => CisTuple::Version1(CisVersion1 { data: tuple }),
+            _ => CisTuple::Raw(tuple),
+        }
+    }
+}
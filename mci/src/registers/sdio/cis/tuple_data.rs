@@ -0,0 +1,17 @@
+/// A raw CIS tuple: its code and body
+pub struct CisTupleData {
+    /// Tuple code (CISTPL_*)
+    pub code: u8,
+    /// Body length, the tuple's link byte
+    pub length: u8,
+    /// Address of the tuple in the CIA
+    pub address: u32,
+    /// Tuple body, only the first `length` bytes are valid
+    pub data: [u8; 255],
+}
+
+impl CisTupleData {
+    pub fn body(&self) -> &[u8] {
+        &self.data[..self.length as usize]
+    }
+}
@@ -0,0 +1,28 @@
+use crate::registers::sdio::cis::tuple_data::CisTupleData;
+
+/// CISTPL_VERS_1 - Level 1 version and product information
+pub struct CisVersion1 {
+    pub data: CisTupleData,
+}
+
+impl CisVersion1 {
+    /// Major version number (TPLLV1_MAJOR)
+    pub fn major(&self) -> u8 {
+        self.data.body()[0]
+    }
+
+    /// Minor version number (TPLLV1_MINOR)
+    pub fn minor(&self) -> u8 {
+        self.data.body()[1]
+    }
+
+    /// Product information strings: manufacturer, product name and additional information.
+    /// Each string is null terminated, the list ends with 0xFF
+    pub fn product_information(&self) -> impl Iterator<Item = &[u8]> {
+        let info = &self.data.body()[2..];
+        let end = info.iter().position(|c| *c == 0xFF).unwrap_or(info.len());
+        info[..end]
+            .split(|c| *c == 0)
+            .filter(|string| !string.is_empty())
+    }
+}
@@ -1,7 +1,8 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
-use core::hint::unreachable_unchecked;
 
+#[derive(Copy, Clone, PartialEq)]
 pub enum SdioInterfaceFunctionCode {
     /// No SDIO standard interface
     None = 0x0,
@@ -21,13 +22,18 @@ pub enum SdioInterfaceFunctionCode {
     Wlan = 0x7,
     /// Embedded Sdio ATA
     EmbeddedSdioAta = 0x8,
+    /// Bluetooth Type A AMP
+    BluetoothAAmp = 0x9,
+    /// Reserved codes 0xA to 0xE
+    Reserved = 0xA,
     /// Check EXT interface code
     CheckExt = 0xF,
 }
 
+/// Only the 4 bits of the code are decoded
 impl From<u8> for SdioInterfaceFunctionCode {
     fn from(val: u8) -> Self {
-        match val {
+        match val.get_bits(0..=3) {
             0x0 => SdioInterfaceFunctionCode::None,
             0x1 => SdioInterfaceFunctionCode::Uart,
             0x2 => SdioInterfaceFunctionCode::BluetoothA,
@@ -37,12 +43,15 @@ impl From<u8> for SdioInterfaceFunctionCode {
             0x6 => SdioInterfaceFunctionCode::Phs,
             0x7 => SdioInterfaceFunctionCode::Wlan,
             0x8 => SdioInterfaceFunctionCode::EmbeddedSdioAta,
-            0xF => SdioInterfaceFunctionCode::CheckExt,
-            _ => unsafe { unreachable_unchecked() },
+            0x9 => SdioInterfaceFunctionCode::BluetoothAAmp,
+            0xA..=0xE => SdioInterfaceFunctionCode::Reserved,
+            // 0xF
+            _ => SdioInterfaceFunctionCode::CheckExt,
         }
     }
 }
 
+#[derive(Copy, Clone)]
 pub struct CsaAndInterfaceCodeRegister {
     pub val: u8,
 }
@@ -78,3 +87,16 @@ impl RegisterAddress for CsaAndInterfaceCodeRegister {
         0x0
     }
 }
+
+impl SdioRegister for CsaAndInterfaceCodeRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Fbr;
+    const WRITABLE_BITS: u8 = 0x80;
+
+    fn from_val(val: u8) -> Self {
+        CsaAndInterfaceCodeRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -2,17 +2,18 @@ use crate::registers::register_address::RegisterAddress;
 use bit_field::BitField;
 
 /// Address pointer to CSA, 3 bytes, LSB first
+/// The pointer increments with each byte accessed through the CSA data window
 pub struct CsaPointerRegister {
     pub val: u32,
 }
 
 impl CsaPointerRegister {
     pub fn set_lsb(&mut self, val: u8) {
-        self.val.set_bits(16..=23, val as u32);
+        self.val.set_bits(0..=7, val as u32);
     }
 
     pub fn lsb(&self) -> u8 {
-        self.val.get_bits(16..=23) as u8
+        self.val.get_bits(0..=7) as u8
     }
 
     pub fn set_mid(&mut self, val: u8) {
@@ -20,20 +21,20 @@ impl CsaPointerRegister {
     }
 
     pub fn mid(&self) -> u8 {
-        self.val.get_bits(16..=23) as u8
+        self.val.get_bits(8..=15) as u8
     }
 
     pub fn set_msb(&mut self, val: u8) {
-        self.val.set_bits(0..=7, val as u32);
+        self.val.set_bits(16..=23, val as u32);
     }
 
     pub fn msb(&self) -> u8 {
-        self.val.get_bits(0..=7) as u8
+        self.val.get_bits(16..=23) as u8
     }
 }
 
 impl RegisterAddress for CsaPointerRegister {
     fn address() -> u8 {
-        0x9
+        0xC
     }
 }
@@ -1,11 +1,33 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 
+#[derive(Copy, Clone)]
 pub struct ExtendedInterfaceCodeRegister {
     pub val: u8,
 }
 
+impl ExtendedInterfaceCodeRegister {
+    /// Extended SDIO standard interface code, used when the interface code is CheckExt
+    pub fn extended_interface_code(&self) -> u8 {
+        self.val
+    }
+}
+
 impl RegisterAddress for ExtendedInterfaceCodeRegister {
     fn address() -> u8 {
         0x1
     }
 }
+
+impl SdioRegister for ExtendedInterfaceCodeRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Fbr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        ExtendedInterfaceCodeRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -1,6 +1,7 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 
-/// Read/Write fifo to CSA
+/// Read/Write fifo to CSA, at the CSA pointer
 pub struct FifoToCsaReadWriteRegister {
     pub val: u8,
 }
@@ -10,3 +11,16 @@ impl RegisterAddress for FifoToCsaReadWriteRegister {
         0xF
     }
 }
+
+impl SdioRegister for FifoToCsaReadWriteRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Fbr;
+    const WRITABLE_BITS: u8 = 0x00;
+
+    fn from_val(val: u8) -> Self {
+        FifoToCsaReadWriteRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
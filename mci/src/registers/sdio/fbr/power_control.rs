@@ -1,6 +1,8 @@
 use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::sdio_register::{SdioRegister, SdioRegisterSpace};
 use bit_field::BitField;
 
+#[derive(Copy, Clone)]
 pub struct PowerControlRegister {
     pub val: u8,
 }
@@ -10,7 +12,7 @@ impl PowerControlRegister {
         self.val.set_bit(0, supports);
     }
 
-    pub fn function_supports_power_control(&mut self) -> bool {
+    pub fn function_supports_power_control(&self) -> bool {
         self.val.get_bit(0)
     }
 
@@ -29,3 +31,16 @@ impl RegisterAddress for PowerControlRegister {
         0x2
     }
 }
+
+impl SdioRegister for PowerControlRegister {
+    const SPACE: SdioRegisterSpace = SdioRegisterSpace::Fbr;
+    const WRITABLE_BITS: u8 = 0x02;
+
+    fn from_val(val: u8) -> Self {
+        PowerControlRegister { val }
+    }
+
+    fn val(&self) -> u8 {
+        self.val
+    }
+}
@@ -1,2 +1,6 @@
+pub mod bluetooth;
 pub mod cccr;
+pub mod cis;
 pub mod fbr;
+pub mod sdio_register;
+pub mod uart;
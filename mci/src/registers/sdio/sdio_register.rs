@@ -0,0 +1,24 @@
+use crate::registers::register_address::RegisterAddress;
+
+/// Register area of the CIA a register belongs to
+#[derive(Copy, Clone, PartialEq)]
+pub enum SdioRegisterSpace {
+    /// Card Common Control Registers, shared by the whole card
+    Cccr,
+    /// Function Basic Registers, at 0x100 * n for function n
+    Fbr,
+}
+
+/// Single byte register of the CCCR or of a FBR, accessed with CMD52 on function 0
+/// The address is relative to the start of the register area
+pub trait SdioRegister: RegisterAddress {
+    /// Area the register lives in
+    const SPACE: SdioRegisterSpace;
+    /// Bits that read back as written. A write is verified on these bits, and is sent without
+    /// read after write when there is none
+    const WRITABLE_BITS: u8;
+
+    fn from_val(val: u8) -> Self;
+
+    fn val(&self) -> u8;
+}
@@ -0,0 +1,56 @@
+use crate::registers::register_address::RegisterAddress;
+use bit_field::BitField;
+
+/// Receive FIFO level that raises the received data interrupt
+#[derive(Copy, Clone, PartialEq)]
+pub enum ReceiveTriggerLevel {
+    _1Byte = 0,
+    _4Bytes = 1,
+    _8Bytes = 2,
+    _14Bytes = 3,
+}
+
+impl ReceiveTriggerLevel {
+    /// Amount of bytes in the receive FIFO at the trigger level
+    pub fn bytes(self) -> usize {
+        match self {
+            ReceiveTriggerLevel::_1Byte => 1,
+            ReceiveTriggerLevel::_4Bytes => 4,
+            ReceiveTriggerLevel::_8Bytes => 8,
+            ReceiveTriggerLevel::_14Bytes => 14,
+        }
+    }
+}
+
+/// FIFO control register (FCR), write only
+pub struct FifoControlRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for FifoControlRegister {
+    fn address() -> u8 {
+        0x02
+    }
+}
+
+impl FifoControlRegister {
+    pub fn set_fifo_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(0, enabled);
+        self
+    }
+
+    pub fn set_reset_receive_fifo(&mut self, reset: bool) -> &mut Self {
+        self.val.set_bit(1, reset);
+        self
+    }
+
+    pub fn set_reset_transmit_fifo(&mut self, reset: bool) -> &mut Self {
+        self.val.set_bit(2, reset);
+        self
+    }
+
+    pub fn set_receive_trigger_level(&mut self, level: ReceiveTriggerLevel) -> &mut Self {
+        self.val.set_bits(6..8, level as u8);
+        self
+    }
+}
@@ -0,0 +1,51 @@
+use crate::registers::register_address::RegisterAddress;
+use bit_field::BitField;
+
+/// Interrupt enable register (IER)
+pub struct UartInterruptEnableRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for UartInterruptEnableRegister {
+    fn address() -> u8 {
+        0x01
+    }
+}
+
+impl UartInterruptEnableRegister {
+    pub fn set_received_data_interrupt_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(0, enabled);
+        self
+    }
+
+    pub fn received_data_interrupt_enabled(&self) -> bool {
+        self.val.get_bit(0)
+    }
+
+    pub fn set_transmitter_empty_interrupt_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(1, enabled);
+        self
+    }
+
+    pub fn transmitter_empty_interrupt_enabled(&self) -> bool {
+        self.val.get_bit(1)
+    }
+
+    pub fn set_line_status_interrupt_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(2, enabled);
+        self
+    }
+
+    pub fn line_status_interrupt_enabled(&self) -> bool {
+        self.val.get_bit(2)
+    }
+
+    pub fn set_modem_status_interrupt_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(3, enabled);
+        self
+    }
+
+    pub fn modem_status_interrupt_enabled(&self) -> bool {
+        self.val.get_bit(3)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::registers::register_address::RegisterAddress;
+use bit_field::BitField;
+
+/// Highest priority interrupt pending
+#[derive(Copy, Clone, PartialEq)]
+pub enum UartInterruptId {
+    ModemStatus = 0x0,
+    TransmitterEmpty = 0x1,
+    ReceivedData = 0x2,
+    LineStatus = 0x3,
+    CharacterTimeout = 0x6,
+    /// Codes a 16550 does not use
+    Unknown = 0x7,
+}
+
+impl From<u8> for UartInterruptId {
+    fn from(val: u8) -> Self {
+        match val {
+            0x0 => UartInterruptId::ModemStatus,
+            0x1 => UartInterruptId::TransmitterEmpty,
+            0x2 => UartInterruptId::ReceivedData,
+            0x3 => UartInterruptId::LineStatus,
+            0x6 => UartInterruptId::CharacterTimeout,
+            _ => UartInterruptId::Unknown,
+        }
+    }
+}
+
+/// Interrupt identification register (IIR), read only
+pub struct InterruptIdentificationRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for InterruptIdentificationRegister {
+    fn address() -> u8 {
+        0x02
+    }
+}
+
+impl InterruptIdentificationRegister {
+    /// Whether an interrupt is pending. The bit is active low
+    pub fn interrupt_pending(&self) -> bool {
+        !self.val.get_bit(0)
+    }
+
+    pub fn interrupt_id(&self) -> UartInterruptId {
+        self.val.get_bits(1..4).into()
+    }
+
+    pub fn fifos_enabled(&self) -> bool {
+        self.val.get_bits(6..8) == 0x3
+    }
+}
@@ -0,0 +1,69 @@
+use crate::registers::register_address::RegisterAddress;
+use bit_field::BitField;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum WordLength {
+    _5Bits = 0,
+    _6Bits = 1,
+    _7Bits = 2,
+    _8Bits = 3,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Parity {
+    None = 0b000,
+    Odd = 0b001,
+    Even = 0b011,
+    Mark = 0b101,
+    Space = 0b111,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum StopBits {
+    One = 0,
+    /// 1.5 stop bits with 5 bit words, 2 otherwise
+    Two = 1,
+}
+
+/// Line control register (LCR)
+pub struct LineControlRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for LineControlRegister {
+    fn address() -> u8 {
+        0x03
+    }
+}
+
+impl LineControlRegister {
+    pub fn set_word_length(&mut self, length: WordLength) -> &mut Self {
+        self.val.set_bits(0..2, length as u8);
+        self
+    }
+
+    pub fn set_stop_bits(&mut self, stop_bits: StopBits) -> &mut Self {
+        self.val.set_bit(2, stop_bits == StopBits::Two);
+        self
+    }
+
+    pub fn set_parity(&mut self, parity: Parity) -> &mut Self {
+        self.val.set_bits(3..6, parity as u8);
+        self
+    }
+
+    pub fn set_break(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(6, enabled);
+        self
+    }
+
+    /// Divisor latch access bit (DLAB). The data and IER addresses access the divisor latch
+    pub fn set_divisor_latch_access(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(7, enabled);
+        self
+    }
+
+    pub fn divisor_latch_access(&self) -> bool {
+        self.val.get_bit(7)
+    }
+}
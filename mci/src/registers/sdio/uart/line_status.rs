@@ -0,0 +1,54 @@
+use crate::registers::register_address::RegisterAddress;
+use bit_field::BitField;
+
+/// Line status register (LSR), read only. Error bits are cleared by reading it
+pub struct LineStatusRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for LineStatusRegister {
+    fn address() -> u8 {
+        0x05
+    }
+}
+
+impl LineStatusRegister {
+    pub fn data_ready(&self) -> bool {
+        self.val.get_bit(0)
+    }
+
+    pub fn overrun_error(&self) -> bool {
+        self.val.get_bit(1)
+    }
+
+    pub fn parity_error(&self) -> bool {
+        self.val.get_bit(2)
+    }
+
+    pub fn framing_error(&self) -> bool {
+        self.val.get_bit(3)
+    }
+
+    pub fn break_interrupt(&self) -> bool {
+        self.val.get_bit(4)
+    }
+
+    /// The transmit FIFO is empty
+    pub fn transmit_holding_empty(&self) -> bool {
+        self.val.get_bit(5)
+    }
+
+    /// The transmit FIFO and the shift register are empty
+    pub fn transmitter_empty(&self) -> bool {
+        self.val.get_bit(6)
+    }
+
+    pub fn receive_fifo_error(&self) -> bool {
+        self.val.get_bit(7)
+    }
+
+    /// Any of the overrun, parity, framing or break bits
+    pub fn errors(&self) -> u8 {
+        self.val & 0x1E
+    }
+}
@@ -0,0 +1,21 @@
+//! SDIO Standard UART registers, 16550 compatible, in the function's register space
+
+pub mod fifo_control;
+pub mod interrupt_enable;
+pub mod interrupt_identification;
+pub mod line_control;
+pub mod line_status;
+pub mod modem_control;
+
+/// Receive buffer (read) and transmit holding (write) register
+pub const SDIO_UART_DATA: u8 = 0x00;
+/// Divisor latch LSB, when the divisor latch is accessible (LCR DLAB)
+pub const SDIO_UART_DIVISOR_LATCH_LSB: u8 = 0x00;
+/// Divisor latch MSB, when the divisor latch is accessible (LCR DLAB)
+pub const SDIO_UART_DIVISOR_LATCH_MSB: u8 = 0x01;
+/// Modem status register
+pub const SDIO_UART_MODEM_STATUS: u8 = 0x06;
+/// Scratch register
+pub const SDIO_UART_SCRATCH: u8 = 0x07;
+/// Depth of the receive and transmit FIFOs
+pub const SDIO_UART_FIFO_SIZE: usize = 16;
@@ -0,0 +1,41 @@
+use crate::registers::register_address::RegisterAddress;
+use bit_field::BitField;
+
+/// Modem control register (MCR)
+pub struct ModemControlRegister {
+    pub val: u8,
+}
+
+impl RegisterAddress for ModemControlRegister {
+    fn address() -> u8 {
+        0x04
+    }
+}
+
+impl ModemControlRegister {
+    pub fn set_data_terminal_ready(&mut self, ready: bool) -> &mut Self {
+        self.val.set_bit(0, ready);
+        self
+    }
+
+    pub fn set_request_to_send(&mut self, request: bool) -> &mut Self {
+        self.val.set_bit(1, request);
+        self
+    }
+
+    pub fn set_out1(&mut self, out: bool) -> &mut Self {
+        self.val.set_bit(2, out);
+        self
+    }
+
+    /// OUT2 gates the UART interrupt on a 16550
+    pub fn set_out2(&mut self, out: bool) -> &mut Self {
+        self.val.set_bit(3, out);
+        self
+    }
+
+    pub fn set_loopback(&mut self, enabled: bool) -> &mut Self {
+        self.val.set_bit(4, enabled);
+        self
+    }
+}
@@ -1,4 +1,4 @@
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
 pub enum SdBusWidth {
     /// 1-bit data bus
@@ -7,12 +7,15 @@ pub enum SdBusWidth {
     _4bit = 4,
 }
 
-impl From<u8> for SdBusWidth {
-    fn from(val: u8) -> Self {
+impl TryFrom<u8> for SdBusWidth {
+    type Error = u8;
+
+    /// Any other value is given back as the error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            1 => SdBusWidth::_1bit,
-            4 => SdBusWidth::_4bit,
-            _ => unsafe { unreachable_unchecked() },
+            1 => Ok(SdBusWidth::_1bit),
+            4 => Ok(SdBusWidth::_4bit),
+            _ => Err(val),
         }
     }
 }
@@ -1,3 +1,5 @@
+use core::convert::TryFrom;
+
 pub enum SdPhysicalSpecification {
     /// SD 1.01 (March 2000)
     Revision1d01 = 0,
@@ -9,14 +11,17 @@ pub enum SdPhysicalSpecification {
     Revision3d00 = 3,
 }
 
-impl From<u8> for SdPhysicalSpecification {
-    fn from(val: u8) -> Self {
+impl TryFrom<u8> for SdPhysicalSpecification {
+    type Error = u8;
+
+    /// A reserved version is given back as the error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            0 => SdPhysicalSpecification::Revision1d01,
-            1 => SdPhysicalSpecification::Revision1d10,
-            2 => SdPhysicalSpecification::Revision2d00,
-            3 => SdPhysicalSpecification::Revision3d00,
-            _ => unreachable!(),
+            0 => Ok(SdPhysicalSpecification::Revision1d01),
+            1 => Ok(SdPhysicalSpecification::Revision1d10),
+            2 => Ok(SdPhysicalSpecification::Revision2d00),
+            3 => Ok(SdPhysicalSpecification::Revision3d00),
+            _ => Err(val),
         }
     }
 }
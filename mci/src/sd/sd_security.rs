@@ -1,4 +1,4 @@
-use core::hint::unreachable_unchecked;
+use core::convert::TryFrom;
 
 pub enum SdSecurity {
     None = 0,
@@ -8,15 +8,18 @@ pub enum SdSecurity {
     _3_00 = 4,
 }
 
-impl From<u8> for SdSecurity {
-    fn from(val: u8) -> Self {
+impl TryFrom<u8> for SdSecurity {
+    type Error = u8;
+
+    /// A reserved version is given back as the error
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            0 => SdSecurity::None,
-            1 => SdSecurity::NotUsed,
-            2 => SdSecurity::_1_01,
-            3 => SdSecurity::_2_00,
-            4 => SdSecurity::_3_00,
-            _ => unsafe { unreachable_unchecked() },
+            0 => Ok(SdSecurity::None),
+            1 => Ok(SdSecurity::NotUsed),
+            2 => Ok(SdSecurity::_1_01),
+            3 => Ok(SdSecurity::_2_00),
+            4 => Ok(SdSecurity::_3_00),
+            _ => Err(val),
         }
     }
 }
@@ -0,0 +1,332 @@
+use crate::functions::sdio_driver::{SdioDeviceId, SdioFunction, SdioFunctionDriver};
+use crate::mci::Mci;
+use crate::registers::sdio::bluetooth::{
+    SDIO_BT_HEADER_SIZE, SDIO_BT_INTERRUPT, SDIO_BT_INTERRUPT_ENABLE,
+    SDIO_BT_INTERRUPT_PACKET_READY, SDIO_BT_PACKET_CONTROL_ACK, SDIO_BT_PACKET_CONTROL_RETRY,
+    SDIO_BT_READ_PACKET_CONTROL, SDIO_BT_RECEIVE_DATA, SDIO_BT_TRANSMIT_DATA,
+    SDIO_BT_WRITE_PACKET_CONTROL,
+};
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::fbr::csa_and_interface::SdioInterfaceFunctionCode;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// Attempts to transfer a packet before giving up
+pub const SDIO_BT_TRANSFER_ATTEMPTS: usize = 3;
+
+static SDIO_BT_IDS: [SdioDeviceId; 2] = [
+    SdioDeviceId::Interface(SdioInterfaceFunctionCode::BluetoothA),
+    SdioDeviceId::Interface(SdioInterfaceFunctionCode::BluetoothAAmp),
+];
+
+/// HCI packet type, the service ID of the Type-A header
+#[derive(Copy, Clone, PartialEq)]
+pub enum HciPacketType {
+    Command = 0x01,
+    AclData = 0x02,
+    ScoData = 0x03,
+    Event = 0x04,
+}
+
+impl HciPacketType {
+    pub fn from_service_id(service_id: u8) -> Option<Self> {
+        match service_id {
+            0x01 => Some(HciPacketType::Command),
+            0x02 => Some(HciPacketType::AclData),
+            0x03 => Some(HciPacketType::ScoData),
+            0x04 => Some(HciPacketType::Event),
+            _ => None,
+        }
+    }
+}
+
+/// HCI transport over an SDIO Type-A Bluetooth function
+/// `N` is the largest HCI packet, without the Type-A header
+/// When the host supports the SDIO interrupt, a received packet is read by the interrupt hook and
+/// kept until it is taken with receive. The card interrupt is masked while a packet is kept
+pub struct SdioBluetooth<const N: usize> {
+    function: Option<FunctionSelection>,
+    interrupt_driven: bool,
+    /// A packet is ready on the card but the receive buffer is full
+    receive_deferred: bool,
+    received: Option<(HciPacketType, usize)>,
+    rx: [u8; N],
+}
+
+impl<const N: usize> SdioBluetooth<N> {
+    pub const fn new() -> Self {
+        SdioBluetooth {
+            function: None,
+            interrupt_driven: false,
+            receive_deferred: false,
+            received: None,
+            rx: [0; N],
+        }
+    }
+
+    /// Function the driver is attached to
+    pub fn function(&self) -> Option<FunctionSelection> {
+        self.function
+    }
+
+    /// Whether packets are read by the interrupt hook
+    pub fn interrupt_driven(&self) -> bool {
+        self.interrupt_driven
+    }
+
+    /// Send an HCI packet
+    /// # Arguments
+    /// * `function` The attached function
+    /// * `packet_type` Command, ACL or SCO data
+    /// * `payload` HCI packet, without the packet type indicator
+    pub fn send<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+        packet_type: HciPacketType,
+        payload: &[u8],
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        self.check_function(function)?;
+        let length = SDIO_BT_HEADER_SIZE + payload.len();
+        if length > 0xFF_FFFF {
+            return Err(MciError::IncorrectDataSize);
+        }
+        let header = [
+            length as u8,
+            (length >> 8) as u8,
+            (length >> 16) as u8,
+            packet_type as u8,
+        ];
+
+        // The packet may span several CMD53, the card uses the length of the header
+        let mut result = Ok(());
+        for _ in 0..SDIO_BT_TRANSFER_ATTEMPTS {
+            result = function
+                .write_extended(SDIO_BT_TRANSMIT_DATA as u32, false, &header)
+                .and_then(|_| {
+                    function.write_extended(SDIO_BT_TRANSMIT_DATA as u32, false, payload)
+                });
+            if result.is_ok() {
+                break;
+            }
+            // Make the card drop what it got of the packet
+            function.write_direct(
+                SDIO_BT_WRITE_PACKET_CONTROL as u32,
+                SDIO_BT_PACKET_CONTROL_RETRY,
+            )?;
+        }
+        result
+    }
+
+    /// Take a received HCI packet
+    /// Without interrupt support, the card is polled for a packet
+    /// Returns the packet type and the length copied to `destination`, None if there is no
+    /// packet
+    pub fn receive<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+        destination: &mut [u8],
+    ) -> Result<Option<(HciPacketType, usize)>, MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        self.check_function(function)?;
+        if self.received.is_none() && (!self.interrupt_driven || self.receive_deferred) {
+            self.service(function)?;
+        }
+        let (packet_type, length) = match self.received {
+            Some(received) => received,
+            None => return Ok(None),
+        };
+        if destination.len() < length {
+            return Err(MciError::IncorrectDataSize);
+        }
+        destination[..length].copy_from_slice(&self.rx[..length]);
+        self.received = None;
+        if self.receive_deferred {
+            // Unmask the card interrupt for the packet that is waiting
+            self.receive_deferred = false;
+            function.write_direct(
+                SDIO_BT_INTERRUPT_ENABLE as u32,
+                SDIO_BT_INTERRUPT_PACKET_READY,
+            )?;
+        }
+        Ok(Some((packet_type, length)))
+    }
+
+    /// Read the packet the card indicates, if the receive buffer is free
+    pub fn service<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        let interrupt = function.read_direct(SDIO_BT_INTERRUPT as u32)?;
+        if interrupt & SDIO_BT_INTERRUPT_PACKET_READY == 0 {
+            return Ok(());
+        }
+        if self.received.is_some() {
+            // Keep the packet on the card until the buffer is taken
+            if !self.receive_deferred {
+                self.receive_deferred = true;
+                function.write_direct(SDIO_BT_INTERRUPT_ENABLE as u32, 0)?;
+            }
+            return Ok(());
+        }
+        function.write_direct(SDIO_BT_INTERRUPT as u32, SDIO_BT_INTERRUPT_PACKET_READY)?;
+
+        let mut result = Err(MciError::ReadError);
+        for _ in 0..SDIO_BT_TRANSFER_ATTEMPTS {
+            result = self.read_packet(function);
+            match &result {
+                Err(MciError::IncorrectDataSize) => break,
+                Err(_) => function.write_direct(
+                    SDIO_BT_READ_PACKET_CONTROL as u32,
+                    SDIO_BT_PACKET_CONTROL_RETRY,
+                )?,
+                Ok(_) => break,
+            }
+        }
+        function.write_direct(
+            SDIO_BT_READ_PACKET_CONTROL as u32,
+            SDIO_BT_PACKET_CONTROL_ACK,
+        )?;
+        self.received = Some(result?);
+        Ok(())
+    }
+
+    /// Read one packet into the receive buffer
+    /// A packet larger than the buffer is read and dropped
+    fn read_packet<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(HciPacketType, usize), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        let mut header = [0u8; SDIO_BT_HEADER_SIZE];
+        function.read_extended(SDIO_BT_RECEIVE_DATA as u32, false, &mut header)?;
+        let length = header[0] as usize | (header[1] as usize) << 8 | (header[2] as usize) << 16;
+        if length < SDIO_BT_HEADER_SIZE {
+            return Err(MciError::ReadError);
+        }
+        let length = length - SDIO_BT_HEADER_SIZE;
+        if length > N {
+            let mut remaining = length;
+            while remaining > 0 {
+                let chunk = remaining.min(N);
+                function.read_extended(
+                    SDIO_BT_RECEIVE_DATA as u32,
+                    false,
+                    &mut self.rx[..chunk],
+                )?;
+                remaining -= chunk;
+            }
+            return Err(MciError::IncorrectDataSize);
+        }
+        function.read_extended(SDIO_BT_RECEIVE_DATA as u32, false, &mut self.rx[..length])?;
+        match HciPacketType::from_service_id(header[3]) {
+            Some(packet_type) => Ok((packet_type, length)),
+            None => Err(MciError::IncorrectDataSize),
+        }
+    }
+
+    fn check_function<MCI, WP, DETECT, POWER, CLOCK>(
+        &self,
+        function: &SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        if self.function != Some(function.function()) {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for SdioBluetooth<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK, const N: usize>
+    SdioFunctionDriver<MCI, WP, DETECT, POWER, CLOCK> for SdioBluetooth<N>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    fn ids(&self) -> &[SdioDeviceId] {
+        &SDIO_BT_IDS
+    }
+
+    fn attach(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        function.enable(None)?;
+        self.received = None;
+        self.receive_deferred = false;
+        function.write_direct(
+            SDIO_BT_INTERRUPT_ENABLE as u32,
+            SDIO_BT_INTERRUPT_PACKET_READY,
+        )?;
+        // Without SDIO interrupt support on the host, packets are polled
+        self.interrupt_driven = match function.enable_interrupt() {
+            Ok(()) => true,
+            Err(MciError::Impl(ImplError::InvalidConfiguration)) => false,
+            Err(err) => return Err(err),
+        };
+        self.function = Some(function.function());
+        Ok(())
+    }
+
+    fn detach(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        self.function = None;
+        function.write_direct(SDIO_BT_INTERRUPT_ENABLE as u32, 0)?;
+        if self.interrupt_driven {
+            self.interrupt_driven = false;
+            function.disable_interrupt()?;
+        }
+        function.disable()
+    }
+
+    fn interrupt(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        self.service(function)
+    }
+}
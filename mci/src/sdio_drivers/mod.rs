@@ -0,0 +1,5 @@
+//! Drivers of SDIO standard functions, bound with functions::sdio_driver::SdioDriverRegistry
+
+pub mod bluetooth;
+mod byte_queue;
+pub mod uart;
@@ -0,0 +1,472 @@
+use crate::functions::sdio_driver::{SdioDeviceId, SdioFunction, SdioFunctionDriver};
+use crate::mci::Mci;
+use crate::registers::register_address::RegisterAddress;
+use crate::registers::sdio::cccr::function_select::FunctionSelection;
+use crate::registers::sdio::fbr::csa_and_interface::SdioInterfaceFunctionCode;
+use crate::registers::sdio::uart::fifo_control::{FifoControlRegister, ReceiveTriggerLevel};
+use crate::registers::sdio::uart::interrupt_enable::UartInterruptEnableRegister;
+use crate::registers::sdio::uart::interrupt_identification::{
+    InterruptIdentificationRegister, UartInterruptId,
+};
+use crate::registers::sdio::uart::line_control::{
+    LineControlRegister, Parity, StopBits, WordLength,
+};
+use crate::registers::sdio::uart::line_status::LineStatusRegister;
+use crate::registers::sdio::uart::modem_control::ModemControlRegister;
+use crate::registers::sdio::uart::{
+    SDIO_UART_DATA, SDIO_UART_DIVISOR_LATCH_LSB, SDIO_UART_DIVISOR_LATCH_MSB, SDIO_UART_FIFO_SIZE,
+    SDIO_UART_MODEM_STATUS,
+};
+use crate::sdio_drivers::byte_queue::ByteQueue;
+use crate::timeout::MonotonicClock;
+use embedded_error::mci::MciError;
+use embedded_error::ImplError;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use embedded_hal::serial;
+
+/// UART clock of a 16550. The baud rate divisor is clock / (16 * baud rate)
+pub const SDIO_UART_DEFAULT_CLOCK: u32 = 1_843_200;
+/// Bytes received and not read yet
+pub const SDIO_UART_RX_BUFFER_SIZE: usize = 256;
+/// Bytes written and not sent to the function yet
+pub const SDIO_UART_TX_BUFFER_SIZE: usize = 64;
+/// Interrupt sources handled in one call to service, in case the function keeps raising one
+const SDIO_UART_MAX_INTERRUPTS: usize = 8;
+
+static SDIO_UART_IDS: [SdioDeviceId; 1] =
+    [SdioDeviceId::Interface(SdioInterfaceFunctionCode::Uart)];
+
+/// Line settings of the UART
+#[derive(Copy, Clone)]
+pub struct SdioUartConfig {
+    pub baud_rate: u32,
+    pub word_length: WordLength,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    /// Receive FIFO level that raises the received data interrupt
+    pub receive_trigger_level: ReceiveTriggerLevel,
+}
+
+impl Default for SdioUartConfig {
+    fn default() -> Self {
+        SdioUartConfig {
+            baud_rate: 115_200,
+            word_length: WordLength::_8Bits,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            receive_trigger_level: ReceiveTriggerLevel::_8Bytes,
+        }
+    }
+}
+
+/// Driver of an SDIO Standard UART function
+/// Received data is buffered by the interrupt hook when the host supports the SDIO interrupt,
+/// otherwise it is polled by SdioUartPort
+pub struct SdioUart {
+    /// UART clock of the function
+    pub clock: u32,
+    /// Line settings applied when attaching
+    pub config: SdioUartConfig,
+    function: Option<FunctionSelection>,
+    interrupt_driven: bool,
+    interrupt_enable: UartInterruptEnableRegister,
+    line_errors: u8,
+    rx: ByteQueue<SDIO_UART_RX_BUFFER_SIZE>,
+    tx: ByteQueue<SDIO_UART_TX_BUFFER_SIZE>,
+}
+
+impl SdioUart {
+    /// # Arguments
+    /// * `clock` UART clock of the function, see SDIO_UART_DEFAULT_CLOCK
+    /// * `config` Line settings applied when attaching
+    pub const fn new(clock: u32, config: SdioUartConfig) -> Self {
+        SdioUart {
+            clock,
+            config,
+            function: None,
+            interrupt_driven: false,
+            interrupt_enable: UartInterruptEnableRegister { val: 0 },
+            line_errors: 0,
+            rx: ByteQueue::new(),
+            tx: ByteQueue::new(),
+        }
+    }
+
+    /// Function the driver is attached to
+    pub fn function(&self) -> Option<FunctionSelection> {
+        self.function
+    }
+
+    /// Whether received data is read by the interrupt hook
+    pub fn interrupt_driven(&self) -> bool {
+        self.interrupt_driven
+    }
+
+    /// Overrun, parity, framing and break bits (LSR) seen since the last call
+    pub fn take_line_errors(&mut self) -> u8 {
+        let errors = self.line_errors;
+        self.line_errors = 0;
+        errors
+    }
+
+    /// Serial port on the attached function
+    pub fn port<'a, 'b, MCI, WP, DETECT, POWER, CLOCK>(
+        &'a mut self,
+        function: SdioFunction<'b, MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<SdioUartPort<'a, 'b, MCI, WP, DETECT, POWER, CLOCK>, MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        if self.function != Some(function.function()) {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        Ok(SdioUartPort {
+            uart: self,
+            function,
+        })
+    }
+
+    /// Set the baud rate and line settings
+    pub fn configure<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+        config: SdioUartConfig,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        if config.baud_rate == 0 {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        let divisor = (self.clock + 8 * config.baud_rate) / (16 * config.baud_rate);
+        if divisor == 0 || divisor > 0xFFFF {
+            return Err(MciError::Impl(ImplError::InvalidConfiguration));
+        }
+        let mut line_control = LineControlRegister { val: 0 };
+        line_control
+            .set_word_length(config.word_length)
+            .set_parity(config.parity)
+            .set_stop_bits(config.stop_bits)
+            .set_divisor_latch_access(true);
+        function.write_direct(LineControlRegister::address() as u32, line_control.val)?;
+        function.write_direct(SDIO_UART_DIVISOR_LATCH_LSB as u32, divisor as u8)?;
+        function.write_direct(SDIO_UART_DIVISOR_LATCH_MSB as u32, (divisor >> 8) as u8)?;
+        line_control.set_divisor_latch_access(false);
+        function.write_direct(LineControlRegister::address() as u32, line_control.val)?;
+        self.config = config;
+        Ok(())
+    }
+
+    /// Handle the pending UART interrupt sources: read received data, send buffered data and
+    /// clear line and modem status
+    pub fn service<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        for _ in 0..SDIO_UART_MAX_INTERRUPTS {
+            let identification = InterruptIdentificationRegister {
+                val: function.read_direct(InterruptIdentificationRegister::address() as u32)?,
+            };
+            if !identification.interrupt_pending() {
+                break;
+            }
+            match identification.interrupt_id() {
+                UartInterruptId::ReceivedData => {
+                    // At least the trigger level is in the receive FIFO
+                    self.receive(function, self.config.receive_trigger_level.bytes())?
+                }
+                UartInterruptId::CharacterTimeout | UartInterruptId::LineStatus => {
+                    self.receive(function, 0)?
+                }
+                UartInterruptId::TransmitterEmpty => self.transmit(function)?,
+                UartInterruptId::ModemStatus | UartInterruptId::Unknown => {
+                    function.read_direct(SDIO_UART_MODEM_STATUS as u32)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the receive FIFO to the receive buffer
+    /// # Arguments
+    /// * `known_available` Bytes known to be in the receive FIFO, read in one fixed address CMD53.
+    ///   The rest is read while the line status reports data ready
+    fn receive<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+        known_available: usize,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        let burst = known_available.min(self.rx.free()).min(SDIO_UART_FIFO_SIZE);
+        if burst > 1 {
+            let mut buf = [0u8; SDIO_UART_FIFO_SIZE];
+            function.read_extended(SDIO_UART_DATA as u32, false, &mut buf[..burst])?;
+            for byte in &buf[..burst] {
+                self.rx.push(*byte);
+            }
+        }
+        loop {
+            // Reading the line status also clears the line status interrupt
+            let line_status = LineStatusRegister {
+                val: function.read_direct(LineStatusRegister::address() as u32)?,
+            };
+            self.line_errors |= line_status.errors();
+            if !line_status.data_ready() || self.rx.free() == 0 {
+                break;
+            }
+            self.rx.push(function.read_direct(SDIO_UART_DATA as u32)?);
+        }
+        Ok(())
+    }
+
+    /// Send the transmit buffer to the transmit FIFO when it is empty, in one fixed address CMD53
+    fn transmit<MCI, WP, DETECT, POWER, CLOCK>(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError>
+    where
+        MCI: Mci,
+        WP: InputPin,
+        DETECT: InputPin,
+        POWER: OutputPin,
+        CLOCK: MonotonicClock,
+    {
+        if !self.tx.is_empty() {
+            let line_status = LineStatusRegister {
+                val: function.read_direct(LineStatusRegister::address() as u32)?,
+            };
+            self.line_errors |= line_status.errors();
+            if line_status.transmit_holding_empty() {
+                let mut buf = [0u8; SDIO_UART_FIFO_SIZE];
+                let mut length = 0;
+                while length < SDIO_UART_FIFO_SIZE {
+                    match self.tx.pop() {
+                        Some(byte) => buf[length] = byte,
+                        None => break,
+                    }
+                    length += 1;
+                }
+                function.write_extended(SDIO_UART_DATA as u32, false, &buf[..length])?;
+            }
+        }
+        // The transmitter empty interrupt is only wanted while there is data to send
+        let wanted = self.interrupt_driven && !self.tx.is_empty();
+        if self.interrupt_enable.transmitter_empty_interrupt_enabled() != wanted {
+            self.interrupt_enable
+                .set_transmitter_empty_interrupt_enabled(wanted);
+            function.write_direct(
+                UartInterruptEnableRegister::address() as u32,
+                self.interrupt_enable.val,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<MCI, WP, DETECT, POWER, CLOCK> SdioFunctionDriver<MCI, WP, DETECT, POWER, CLOCK> for SdioUart
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    fn ids(&self) -> &[SdioDeviceId] {
+        &SDIO_UART_IDS
+    }
+
+    fn attach(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        function.enable(None)?;
+        self.rx.clear();
+        self.tx.clear();
+        self.line_errors = 0;
+
+        let mut fifo_control = FifoControlRegister { val: 0 };
+        fifo_control
+            .set_fifo_enabled(true)
+            .set_reset_receive_fifo(true)
+            .set_reset_transmit_fifo(true)
+            .set_receive_trigger_level(self.config.receive_trigger_level);
+        function.write_direct(FifoControlRegister::address() as u32, fifo_control.val)?;
+        self.configure(function, self.config)?;
+
+        let mut modem_control = ModemControlRegister { val: 0 };
+        modem_control
+            .set_data_terminal_ready(true)
+            .set_request_to_send(true)
+            .set_out2(true);
+        function.write_direct(ModemControlRegister::address() as u32, modem_control.val)?;
+
+        self.interrupt_enable = UartInterruptEnableRegister { val: 0 };
+        self.interrupt_enable
+            .set_received_data_interrupt_enabled(true)
+            .set_line_status_interrupt_enabled(true);
+        function.write_direct(
+            UartInterruptEnableRegister::address() as u32,
+            self.interrupt_enable.val,
+        )?;
+        // Without SDIO interrupt support on the host, received data is polled
+        self.interrupt_driven = match function.enable_interrupt() {
+            Ok(()) => true,
+            Err(MciError::Impl(ImplError::InvalidConfiguration)) => false,
+            Err(err) => return Err(err),
+        };
+        self.function = Some(function.function());
+        Ok(())
+    }
+
+    fn detach(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        self.function = None;
+        self.interrupt_enable = UartInterruptEnableRegister { val: 0 };
+        function.write_direct(UartInterruptEnableRegister::address() as u32, 0)?;
+        if self.interrupt_driven {
+            self.interrupt_driven = false;
+            function.disable_interrupt()?;
+        }
+        function.disable()
+    }
+
+    fn interrupt(
+        &mut self,
+        function: &mut SdioFunction<MCI, WP, DETECT, POWER, CLOCK>,
+    ) -> Result<(), MciError> {
+        self.service(function)
+    }
+}
+
+/// embedded-hal serial port on an SDIO UART function
+/// Written bytes are buffered and sent when the buffer is full, on flush, or from the
+/// interrupt hook
+pub struct SdioUartPort<'a, 'b, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    uart: &'a mut SdioUart,
+    function: SdioFunction<'b, MCI, WP, DETECT, POWER, CLOCK>,
+}
+
+impl<'a, 'b, MCI, WP, DETECT, POWER, CLOCK> SdioUartPort<'a, 'b, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    /// Service the UART when the card interrupt is pending, in case the interrupt hook is not
+    /// called, or not called yet, by the application
+    fn service_pending(&mut self) -> Result<(), MciError> {
+        if self.uart.interrupt_driven && self.function.card_interrupt_pending()? {
+            self.uart.service(&mut self.function)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a, 'b, MCI, WP, DETECT, POWER, CLOCK> serial::Read<u8>
+    for SdioUartPort<'a, 'b, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    type Error = MciError;
+
+    fn read(&mut self) -> nb::Result<u8, MciError> {
+        if self.uart.rx.is_empty() {
+            if self.uart.interrupt_driven {
+                self.service_pending().map_err(nb::Error::Other)?;
+            } else {
+                self.uart
+                    .receive(&mut self.function, 0)
+                    .map_err(nb::Error::Other)?;
+            }
+        }
+        self.uart.rx.pop().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl<'a, 'b, MCI, WP, DETECT, POWER, CLOCK> serial::Write<u8>
+    for SdioUartPort<'a, 'b, MCI, WP, DETECT, POWER, CLOCK>
+where
+    MCI: Mci,
+    WP: InputPin,
+    DETECT: InputPin,
+    POWER: OutputPin,
+    CLOCK: MonotonicClock,
+{
+    type Error = MciError;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), MciError> {
+        if self.uart.tx.free() == 0 {
+            self.uart
+                .transmit(&mut self.function)
+                .map_err(nb::Error::Other)?;
+        }
+        if !self.uart.tx.push(word) {
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.uart.tx.len() == 1 && self.uart.interrupt_driven {
+            // Let the transmitter empty interrupt send it
+            self.uart
+                .transmit(&mut self.function)
+                .map_err(nb::Error::Other)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), MciError> {
+        self.service_pending().map_err(nb::Error::Other)?;
+        self.uart
+            .transmit(&mut self.function)
+            .map_err(nb::Error::Other)?;
+        if !self.uart.tx.is_empty() {
+            return Err(nb::Error::WouldBlock);
+        }
+        let line_status = LineStatusRegister {
+            val: self
+                .function
+                .read_direct(LineStatusRegister::address() as u32)
+                .map_err(nb::Error::Other)?,
+        };
+        if line_status.transmitter_empty() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
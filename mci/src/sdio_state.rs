@@ -24,6 +24,7 @@ impl From<u16> for SdioR5StateValue {
     }
 }
 
+#[derive(Copy, Clone)]
 pub struct SdioR5State {
     pub val: u16,
 }
@@ -52,11 +53,11 @@ impl SdioR5State {
     }
 
     pub fn set_state(&mut self, val: SdioR5StateValue) {
-        self.val.set_bits(12..13, val.into());
+        self.val.set_bits(12..14, val.into());
     }
 
     pub fn state(&self) -> SdioR5StateValue {
-        self.val.get_bits(12..13).into()
+        self.val.get_bits(12..14).into()
     }
 
     pub fn set_general_error(&mut self, error: bool) {
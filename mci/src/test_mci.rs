@@ -0,0 +1,143 @@
+use crate::command_arguments::mmc::BusWidth;
+use crate::command_arguments::sdio::cmd52::Cmd52;
+use crate::commands::SDIO_CMD52_IO_RW_DIRECT;
+use crate::dummy_input_pin::DummyInputPin;
+use crate::dummy_output_pin::DummyOutputPin;
+use crate::mci::Mci;
+use crate::mci_card::MciCard;
+use embedded_error::mci::MciError;
+
+/// Host for the unit tests. CMD52 reads are answered from a byte image of the CIA starting at
+/// cia_start, addresses outside of it read 0. Every other command succeeds with a 0 response
+pub struct TestMci {
+    pub cia: [u8; 64],
+    pub cia_start: u32,
+    response: u32,
+}
+
+impl TestMci {
+    pub fn new(cia_start: u32, cia: &[u8]) -> Self {
+        let mut mci = TestMci {
+            cia: [0; 64],
+            cia_start,
+            response: 0,
+        };
+        mci.cia[..cia.len()].copy_from_slice(cia);
+        mci
+    }
+}
+
+pub type TestCard = MciCard<TestMci, DummyInputPin, DummyInputPin, DummyOutputPin>;
+
+/// A card on a TestMci, without write protection and always inserted
+pub fn test_card(mci: TestMci) -> TestCard {
+    MciCard::new(
+        mci,
+        DummyInputPin { high: false },
+        true,
+        DummyInputPin { high: true },
+        true,
+        0,
+    )
+}
+
+impl Mci for TestMci {
+    fn init(&mut self) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn send_command(&mut self, cmd: u32, arg: u32) -> Result<(), MciError> {
+        let cmd52: u32 = SDIO_CMD52_IO_RW_DIRECT.into();
+        self.response = 0;
+        if cmd == cmd52 {
+            let address = Cmd52 { val: arg }.register_address();
+            if let Some(offset) = address.checked_sub(self.cia_start) {
+                if let Some(&byte) = self.cia.get(offset as usize) {
+                    self.response = byte as u32;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn deinit(&mut self) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn select_device(
+        &mut self,
+        _slot: u8,
+        _clock: u32,
+        _bus_width: &BusWidth,
+        _high_speed: bool,
+    ) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn deselect_device(&mut self, _slot: u8) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn get_bus_width(&mut self, _slot: u8) -> Result<BusWidth, MciError> {
+        Ok(BusWidth::_1BIT)
+    }
+
+    fn is_high_speed_capable(&mut self) -> Result<bool, MciError> {
+        Ok(false)
+    }
+
+    fn send_clock(&mut self) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn get_response(&mut self) -> Result<u32, MciError> {
+        Ok(self.response)
+    }
+
+    fn get_response128(&mut self) -> Result<[u32; 4], MciError> {
+        Ok([0; 4])
+    }
+
+    fn adtc_start(
+        &mut self,
+        _command: u32,
+        _argument: u32,
+        _block_size: u16,
+        _block_amount: u16,
+        _access_in_blocks: bool,
+    ) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn adtc_stop(&self, _command: u32, _argument: u32) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn read_word(&mut self) -> Result<(u32, u8), MciError> {
+        Ok((0, 4))
+    }
+
+    fn write_word(&mut self, _val: u32) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
+    fn read_blocks(
+        &mut self,
+        _destination: &mut [u8],
+        _number_of_blocks: u16,
+    ) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
+    fn write_blocks(&mut self, _data: &[u8], _number_of_blocks: u16) -> Result<bool, MciError> {
+        Ok(true)
+    }
+
+    fn wait_until_read_finished(&self) -> Result<(), MciError> {
+        Ok(())
+    }
+
+    fn wait_until_write_finished(&self) -> Result<(), MciError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+/// Monotonic time source in microseconds, for example a free running timer of the MCU
+/// It may wrap, only the time between two readings is used
+pub trait MonotonicClock {
+    /// Current time in us
+    fn now_us(&self) -> u32;
+}
+
+/// A function or closure returning the time in us is a clock
+impl<F> MonotonicClock for F
+where
+    F: Fn() -> u32,
+{
+    fn now_us(&self) -> u32 {
+        self()
+    }
+}
+
+/// Clock of a card without a time source, the timeouts are amounts of polls
+pub enum NoClock {}
+
+impl MonotonicClock for NoClock {
+    fn now_us(&self) -> u32 {
+        match *self {}
+    }
+}
+
+/// Initialization (ACMD41, CMD1, CMD5) of any card
+pub const SD_MMC_INIT_TIMEOUT_US: u32 = 1_000_000;
+/// Read of an SD card
+pub const SD_READ_TIMEOUT_US: u32 = 100_000;
+/// Write of an SDSC or SDHC card
+pub const SD_WRITE_TIMEOUT_US: u32 = 250_000;
+/// Write of an SDXC card
+pub const SDXC_WRITE_TIMEOUT_US: u32 = 500_000;
+/// Time the card detect pin needs to settle after a card is inserted
+pub const SD_MMC_DEBOUNCE_TIMEOUT_US: u32 = 1_000_000;
+/// Polls of the card detect pin that show a card before it is settled, without a monotonic
+/// clock. The debounce time at a 100ms poll period
+pub const SD_MMC_DEBOUNCE_POLLS: u16 = 10;
+/// Supply ramp up of at most 35ms, then 1ms before the card gets its first clocks
+pub const SD_MMC_POWER_RAMP_UP_US: u32 = 36_000;
+/// Time the supply stays off to reset the card, it has to drop below 0.5V for at least 1ms
+pub const SD_MMC_POWER_RAMP_DOWN_US: u32 = 10_000;
+
+/// Deadline of a wait on the card
+/// With a monotonic clock the deadline is in time. Without one it is an amount of polls, each
+/// lasting at least cycles_per_poll cycles of the bus clock
+/// The clock is not kept, it is given again to each expired
+pub struct Timeout {
+    start_us: Option<u32>,
+    duration_us: u32,
+    polls: u64,
+}
+
+impl Timeout {
+    /// # Arguments
+    /// * `clock` Monotonic clock, if any
+    /// * `duration_us` Time to wait
+    /// * `bus_clock` Clock of the card bus in Hz, used without a monotonic clock
+    /// * `cycles_per_poll` Bus clock cycles of a poll (command and response), used without a
+    ///   monotonic clock
+    pub fn new<CLOCK: MonotonicClock>(
+        clock: Option<&CLOCK>,
+        duration_us: u32,
+        bus_clock: u32,
+        cycles_per_poll: u32,
+    ) -> Timeout {
+        Timeout {
+            start_us: clock.map(|clock| clock.now_us()),
+            duration_us,
+            polls: ((duration_us as u64) * (bus_clock as u64)
+                / (1_000_000 * cycles_per_poll.max(1) as u64))
+                .max(2),
+        }
+    }
+
+    /// Whether the deadline passed, to be checked once after every poll
+    /// # Arguments
+    /// * `clock` The clock the timeout was created with
+    pub fn expired<CLOCK: MonotonicClock>(&mut self, clock: Option<&CLOCK>) -> bool {
+        match (clock, self.start_us) {
+            (Some(clock), Some(start_us)) => {
+                clock.now_us().wrapping_sub(start_us) >= self.duration_us
+            }
+            _ => {
+                self.polls = self.polls.saturating_sub(1);
+                self.polls == 0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[test]
+    fn clock_deadline_survives_the_wrap() {
+        let now = Cell::new(u32::MAX - 10);
+        let clock = || now.get();
+        let mut timeout = Timeout::new(Some(&clock), 100, 400_000, 96);
+        assert!(!timeout.expired(Some(&clock)));
+        now.set(88);
+        assert!(!timeout.expired(Some(&clock)));
+        now.set(89);
+        assert!(timeout.expired(Some(&clock)));
+    }
+
+    #[test]
+    fn clock_deadline_ignores_the_amount_of_polls() {
+        let now = Cell::new(0);
+        let clock = || now.get();
+        let mut timeout = Timeout::new(Some(&clock), 1_000, 400_000, 96);
+        for _ in 0..100 {
+            assert!(!timeout.expired(Some(&clock)));
+        }
+        now.set(1_000);
+        assert!(timeout.expired(Some(&clock)));
+    }
+
+    #[test]
+    fn without_clock_polls_are_counted() {
+        // 10ms at 400KHz is 4000 cycles, 41 polls of 96 cycles
+        let mut timeout = Timeout::new(None::<&NoClock>, 10_000, 400_000, 96);
+        for _ in 0..40 {
+            assert!(!timeout.expired(None::<&NoClock>));
+        }
+        assert!(timeout.expired(None::<&NoClock>));
+    }
+
+    #[test]
+    fn without_clock_at_least_one_poll_is_waited() {
+        let mut timeout = Timeout::new(None::<&NoClock>, 0, 400_000, 96);
+        assert!(!timeout.expired(None::<&NoClock>));
+        assert!(timeout.expired(None::<&NoClock>));
+    }
+}
@@ -1,4 +1,62 @@
+/// Step a block transfer is at
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TransferPhase {
+    /// CMD13, waiting for the card to be ready for data
+    WaitReady,
+    /// The read or write command is to be sent
+    Command,
+    /// Blocks are going over the data line
+    Data,
+    /// All the blocks are moved, waiting for the host to end the data transfer, which for a write
+    /// includes the card busy
+    DataEnd,
+    /// CMD12, stopping an open ended multi block transfer
+    Stop,
+    /// CMD13, waiting for the card to finish programming the written blocks
+    Busy,
+    /// All the blocks are transferred
+    Done,
+    /// The transfer stopped on an error
+    Failed,
+}
+
 pub struct TransferTransaction {
     pub amount: u16,
     pub remaining: u16,
+    /// The amount of blocks was set up front with CMD23, no CMD12 is needed to stop the transfer
+    pub block_count_set: bool,
+    /// Step the transfer is at
+    pub phase: TransferPhase,
+    /// First block of the transfer
+    pub lba: u32,
+}
+
+impl TransferTransaction {
+    /// Transaction of a transfer that is yet to start
+    /// # Arguments
+    /// * `lba` First block
+    /// * `amount` Amount of blocks
+    pub fn new(lba: u32, amount: u16) -> TransferTransaction {
+        TransferTransaction {
+            amount,
+            remaining: amount,
+            block_count_set: false,
+            phase: TransferPhase::WaitReady,
+            lba,
+        }
+    }
+
+    /// Amount of blocks already transferred
+    pub fn transferred(&self) -> u16 {
+        self.amount - self.remaining
+    }
+
+    /// Whether the transfer is ended with CMD12
+    /// The card stops by itself after a single block or the amount of blocks set with CMD23,
+    /// unless the transfer is aborted
+    /// # Arguments
+    /// * `abort` The transfer is aborted before all the blocks are transferred
+    pub fn needs_stop(&self, abort: bool) -> bool {
+        self.amount > 1 && (!self.block_count_set || abort)
+    }
 }